@@ -0,0 +1,185 @@
+#[macro_use]
+extern crate criterion;
+extern crate edn;
+
+use criterion::{Criterion, Throughput};
+
+use edn::parser::Parser;
+use edn::reader::{PushParser, ReaderParser};
+use edn::Value;
+
+// A vector of records whose keys are a few repeated keywords, like a typical
+// EDN export.
+fn records(count: usize) -> String {
+    let mut str = String::from("[");
+    for i in 0..count {
+        str.push_str(&format!(
+            "{{:id {} :user/name \"user-{}\" :user/roles #{{:admin :staff}} \
+             :status :status/active :score {}.5 :tags [:a :b :c]}}\n",
+            i,
+            i,
+            i % 100
+        ));
+    }
+    str.push(']');
+    str
+}
+
+// Log entries dominated by long strings, some of them with escapes.
+fn logs(count: usize) -> String {
+    let mut str = String::new();
+    for i in 0..count {
+        str.push_str(&format!(
+            "{{:level :info :at #inst \"2018-03-{:02}T10:00:00Z\" \
+             :msg \"request {} served in {}ms by worker pool, \
+             no retries were needed and the cache was warm\" \
+             :trace \"GET /api/v1/items?page={}\\n\\tat handler\"}}\n",
+            i % 28 + 1,
+            i,
+            i % 1000,
+            i
+        ));
+    }
+    str
+}
+
+// Vectors of integers and floats.
+fn numbers(count: usize) -> String {
+    let mut str = String::new();
+    for i in 0..count {
+        str.push_str(&format!(
+            "[{} {} -{} {}.25 -{}.125 {}]\n",
+            i,
+            i * 31,
+            i * 7,
+            i,
+            i * 3,
+            i * 1_000_003
+        ));
+    }
+    str
+}
+
+// Clojure-like source code with comments and deep nesting.
+fn code(count: usize) -> String {
+    let mut str = String::new();
+    for i in 0..count {
+        str.push_str(&format!(
+            ";; Computes step {}.\n\
+             (defn step-{} [state {{:keys [x y] :as opts}}]\n  \
+               (let [z (+ x y {})] ; add the offset\n    \
+                 (if (> z 10) (update state :count inc) \
+             (assoc-in state [:history z] (str \"z=\" z \\newline)))))\n",
+            i, i, i
+        ));
+    }
+    str
+}
+
+fn read_all(str: &str) -> usize {
+    let mut parser = Parser::new(str);
+    let mut count = 0;
+    while let Some(value) = parser.read() {
+        value.unwrap();
+        count += 1;
+    }
+    count
+}
+
+fn read(str: &str) -> Value {
+    Parser::new(str).read().unwrap().unwrap()
+}
+
+fn keywords(value: &Value, out: &mut Vec<Value>) {
+    match value {
+        Value::Keyword(_) => out.push(value.clone()),
+        Value::Vector(items) | Value::List(items) => items.iter().for_each(|v| keywords(v, out)),
+        Value::Map(map) => map.iter().for_each(|(k, v)| {
+            keywords(k, out);
+            keywords(v, out);
+        }),
+        Value::Set(set) => set.iter().for_each(|v| keywords(v, out)),
+        _ => {}
+    }
+}
+
+fn bench_read(c: &mut Criterion) {
+    let corpora = [
+        ("records", records(10_000)),
+        ("logs", logs(10_000)),
+        ("numbers", numbers(20_000)),
+        ("code", code(5_000)),
+    ];
+    let mut group = c.benchmark_group("read");
+    for (name, str) in corpora.iter() {
+        group.throughput(Throughput::Bytes(str.len() as u64));
+        group.bench_function(*name, |b| b.iter(|| read_all(str)));
+    }
+    group.finish();
+}
+
+fn bench_read_borrowed(c: &mut Criterion) {
+    let str = logs(10_000);
+    let mut group = c.benchmark_group("read_borrowed");
+    group.throughput(Throughput::Bytes(str.len() as u64));
+    group.bench_function("logs", |b| {
+        b.iter(|| {
+            let mut parser = Parser::new(&str);
+            let mut count = 0;
+            while let Some(value) = parser.read_borrowed() {
+                value.unwrap();
+                count += 1;
+            }
+            count
+        })
+    });
+    group.finish();
+}
+
+// Streams the logs corpus, which is many small top-level values.
+fn bench_stream(c: &mut Criterion) {
+    let str = logs(10_000);
+    let mut group = c.benchmark_group("stream");
+    group.throughput(Throughput::Bytes(str.len() as u64));
+    group.bench_function("reader", |b| {
+        b.iter(|| {
+            let mut parser = ReaderParser::new(str.as_bytes());
+            let mut count = 0;
+            while let Some(value) = parser.read() {
+                value.unwrap();
+                count += 1;
+            }
+            count
+        })
+    });
+    group.bench_function("push_1k_chunks", |b| {
+        b.iter(|| {
+            let mut parser = PushParser::new();
+            let mut count = 0;
+            for chunk in str.as_bytes().chunks(1024) {
+                count += parser.feed(chunk).map(Result::unwrap).count();
+            }
+            count + parser.finish().count()
+        })
+    });
+    group.finish();
+}
+
+fn bench_compare_keywords(c: &mut Criterion) {
+    let mut all = vec![];
+    keywords(&read(&records(1_000)), &mut all);
+    let needle = Value::Keyword("status/active".into());
+    let needle = all.iter().find(|k| **k == needle).unwrap().clone();
+    c.bench_function("compare_keywords", |b| {
+        b.iter(|| all.iter().filter(|k| **k == needle).count())
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_read,
+    bench_read_borrowed,
+    bench_stream,
+    bench_compare_keywords
+);
+criterion_main!(benches);
@@ -0,0 +1,69 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+
+use ordered_float::OrderedFloat;
+
+use {Ratio, Value};
+
+/// A `Value` that borrows its strings, symbols, keywords and tags from the
+/// input it was read from, as returned by `Parser::read_borrowed`.
+///
+/// Strings are only owned if they contained escapes. Collections are always
+/// the standard library ones, whatever features are enabled.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BorrowedValue<'a> {
+    Nil,
+    Boolean(bool),
+    String(Cow<'a, str>),
+    Char(char),
+    Symbol(Cow<'a, str>),
+    Keyword(Cow<'a, str>),
+    Integer(i64),
+    Float(OrderedFloat<f64>),
+    Ratio(Ratio),
+    List(Vec<BorrowedValue<'a>>),
+    Vector(Vec<BorrowedValue<'a>>),
+    Map(BTreeMap<BorrowedValue<'a>, BorrowedValue<'a>>),
+    Set(BTreeSet<BorrowedValue<'a>>),
+    Tagged(Cow<'a, str>, Box<BorrowedValue<'a>>),
+}
+
+impl<'a> BorrowedValue<'a> {
+    /// Copies everything borrowed from the input into an owned `Value`.
+    pub fn into_owned(self) -> Value {
+        match self {
+            BorrowedValue::Nil => Value::Nil,
+            BorrowedValue::Boolean(b) => Value::Boolean(b),
+            BorrowedValue::String(s) => Value::String(s.into_owned()),
+            BorrowedValue::Char(ch) => Value::Char(ch),
+            BorrowedValue::Symbol(s) => Value::Symbol((*s).into()),
+            BorrowedValue::Keyword(s) => Value::Keyword((*s).into()),
+            BorrowedValue::Integer(i) => Value::Integer(i),
+            BorrowedValue::Float(f) => Value::Float(f),
+            BorrowedValue::Ratio(r) => Value::Ratio(r),
+            BorrowedValue::List(items) => {
+                Value::List(items.into_iter().map(BorrowedValue::into_owned).collect())
+            }
+            BorrowedValue::Vector(items) => {
+                Value::Vector(items.into_iter().map(BorrowedValue::into_owned).collect())
+            }
+            BorrowedValue::Map(map) => Value::Map(
+                map.into_iter()
+                    .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                    .collect(),
+            ),
+            BorrowedValue::Set(set) => {
+                Value::Set(set.into_iter().map(BorrowedValue::into_owned).collect())
+            }
+            BorrowedValue::Tagged(tag, value) => {
+                Value::Tagged(tag.into_owned(), Box::new(value.into_owned()))
+            }
+        }
+    }
+}
+
+impl<'a> From<BorrowedValue<'a>> for Value {
+    fn from(value: BorrowedValue<'a>) -> Self {
+        value.into_owned()
+    }
+}
@@ -0,0 +1,249 @@
+//! Clojure's equality (`=`) and hashing (`hash`) semantics.
+//!
+//! `Value`'s own `PartialEq` and `Hash` are structural: `(1 2)` and `[1 2]` are
+//! different values. Clojure considers all sequential collections with equal
+//! items equal and hashes them identically, using the Murmur3 based `hasheq`.
+//! The functions here reproduce both bit for bit, so that hashes computed in
+//! Rust can be shared with Clojure code.
+//!
+//! Tagged values hash like the `clojure.lang.TaggedLiteral` that
+//! `clojure.edn/read` returns with `{:default tagged-literal}`.
+
+use {Map, Set, Value};
+
+impl Value {
+    /// Returns whether `self` and `other` are equal according to Clojure's `=`.
+    ///
+    /// Unlike `==`, lists and vectors with equal items are equal, and floats
+    /// compare as IEEE 754 numbers, so `##NaN` isn't equal to itself.
+    pub fn clojure_eq(&self, other: &Value) -> bool {
+        equiv(self, other)
+    }
+
+    /// Returns Clojure's `(hash value)`.
+    ///
+    /// As in Clojure, `0.0` and `-0.0` are equal but hash differently.
+    pub fn clojure_hash(&self) -> i32 {
+        hasheq(self)
+    }
+}
+
+fn equiv(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::List(xs), Value::List(ys))
+        | (Value::List(xs), Value::Vector(ys))
+        | (Value::Vector(xs), Value::List(ys))
+        | (Value::Vector(xs), Value::Vector(ys)) => {
+            xs.len() == ys.len() && xs.iter().zip(ys.iter()).all(|(x, y)| equiv(x, y))
+        }
+        (Value::Float(x), Value::Float(y)) => x.0 == y.0,
+        (Value::Map(x), Value::Map(y)) => x.len() == y.len() && map_equiv(x, y),
+        (Value::Set(x), Value::Set(y)) => x.len() == y.len() && set_equiv(x, y),
+        (Value::Tagged(t, x), Value::Tagged(u, y)) => t == u && equiv(x, y),
+        _ => a == b,
+    }
+}
+
+// Keys that are structurally equal are also equal in Clojure, so a lookup finds
+// most keys; only keys like `(1 2)` vs `[1 2]` need a scan.
+fn map_equiv(x: &Map, y: &Map) -> bool {
+    x.iter().all(|(key, value)| match y.get(key) {
+        Some(other) => equiv(value, other),
+        None => y.iter().any(|(k, v)| equiv(key, k) && equiv(value, v)),
+    })
+}
+
+fn set_equiv(x: &Set, y: &Set) -> bool {
+    x.iter()
+        .all(|item| y.contains(item) || y.iter().any(|other| equiv(item, other)))
+}
+
+// `clojure.lang.Util.hasheq`.
+fn hasheq(value: &Value) -> i32 {
+    match value {
+        Value::String(s) => murmur3::hash_int(string_hash(s)),
+        Value::Symbol(s) => symbol_hasheq(s),
+        Value::Keyword(s) => keyword_hasheq(s),
+        Value::Integer(i) => murmur3::hash_long(*i),
+        Value::List(items) | Value::Vector(items) => {
+            murmur3::hash_ordered(items.iter().map(hasheq))
+        }
+        Value::Map(map) => murmur3::hash_unordered(map.iter().map(|(key, value)| {
+            murmur3::hash_ordered(vec![hasheq(key), hasheq(value)].into_iter())
+        })),
+        Value::Set(set) => murmur3::hash_unordered(set.iter().map(hasheq)),
+        _ => hash_code(value),
+    }
+}
+
+// `Object.hashCode` of the Java object Clojure reads `value` into.
+fn hash_code(value: &Value) -> i32 {
+    match value {
+        Value::Nil => 0,
+        Value::Boolean(true) => 1231,
+        Value::Boolean(false) => 1237,
+        Value::String(s) => string_hash(s),
+        Value::Char(ch) => *ch as i32,
+        Value::Symbol(s) => symbol_hash_code(s),
+        Value::Keyword(s) => symbol_hash_code(s).wrapping_add(0x9e37_79b9_u32 as i32),
+        Value::Integer(i) => (i ^ ((*i as u64) >> 32) as i64) as i32,
+        Value::Float(f) => {
+            // `Double.doubleToLongBits` collapses all NaNs into one.
+            let bits = if f.is_nan() {
+                0x7ff8_0000_0000_0000
+            } else {
+                f.0.to_bits()
+            };
+            (bits ^ (bits >> 32)) as i32
+        }
+        Value::Ratio(r) => big_integer_hash(r.numer()) ^ big_integer_hash(r.denom()),
+        Value::List(items) | Value::Vector(items) => items.iter().fold(1i32, |hash, item| {
+            hash.wrapping_mul(31).wrapping_add(hash_code(item))
+        }),
+        Value::Map(map) => map.iter().fold(0i32, |hash, (key, value)| {
+            hash.wrapping_add(hash_code(key) ^ hash_code(value))
+        }),
+        Value::Set(set) => set
+            .iter()
+            .fold(0i32, |hash, item| hash.wrapping_add(hash_code(item))),
+        Value::Tagged(tag, value) => symbol_hash_code(tag)
+            .wrapping_mul(31)
+            .wrapping_add(hash_code(value)),
+    }
+}
+
+// `BigInteger.hashCode` of `i`, which hashes its magnitude's 32-bit words
+// from the most significant one.
+fn big_integer_hash(i: i64) -> i32 {
+    let magnitude = i.unsigned_abs();
+    let (high, low) = ((magnitude >> 32) as i32, magnitude as i32);
+    high.wrapping_mul(31)
+        .wrapping_add(low)
+        .wrapping_mul(i.signum() as i32)
+}
+
+// `String.hashCode`, which works on UTF-16 code units.
+fn string_hash(s: &str) -> i32 {
+    s.encode_utf16().fold(0i32, |hash, unit| {
+        hash.wrapping_mul(31).wrapping_add(unit as i32)
+    })
+}
+
+// Splits a symbol into its namespace and name like `Symbol.intern`.
+fn split_symbol(s: &str) -> (Option<&str>, &str) {
+    match s.find('/') {
+        Some(index) if s != "/" => (Some(&s[..index]), &s[index + 1..]),
+        _ => (None, s),
+    }
+}
+
+fn symbol_hasheq(s: &str) -> i32 {
+    let (ns, name) = split_symbol(s);
+    hash_combine(
+        murmur3::hash_unencoded_chars(name),
+        ns.map_or(0, string_hash),
+    )
+}
+
+fn symbol_hash_code(s: &str) -> i32 {
+    let (ns, name) = split_symbol(s);
+    hash_combine(string_hash(name), ns.map_or(0, string_hash))
+}
+
+fn keyword_hasheq(s: &str) -> i32 {
+    symbol_hasheq(s).wrapping_add(0x9e37_79b9_u32 as i32)
+}
+
+// `clojure.lang.Util.hashCombine`.
+fn hash_combine(seed: i32, hash: i32) -> i32 {
+    seed ^ hash
+        .wrapping_add(0x9e37_79b9_u32 as i32)
+        .wrapping_add(seed << 6)
+        .wrapping_add(seed >> 2)
+}
+
+// A port of `clojure.lang.Murmur3`, which is Murmur3_32 with a seed of 0.
+mod murmur3 {
+    const C1: i32 = 0xcc9e_2d51_u32 as i32;
+    const C2: i32 = 0x1b87_3593;
+
+    pub fn hash_int(input: i32) -> i32 {
+        if input == 0 {
+            return 0;
+        }
+        let h1 = mix_h1(0, mix_k1(input));
+        fmix(h1, 4)
+    }
+
+    pub fn hash_long(input: i64) -> i32 {
+        if input == 0 {
+            return 0;
+        }
+        let low = input as i32;
+        let high = ((input as u64) >> 32) as i32;
+        let h1 = mix_h1(0, mix_k1(low));
+        let h1 = mix_h1(h1, mix_k1(high));
+        fmix(h1, 8)
+    }
+
+    // Works on UTF-16 code units, two at a time.
+    pub fn hash_unencoded_chars(input: &str) -> i32 {
+        let units = input.encode_utf16().collect::<Vec<_>>();
+        let mut h1 = 0;
+        for pair in units.chunks(2) {
+            if pair.len() == 2 {
+                let k1 = pair[0] as i32 | ((pair[1] as i32) << 16);
+                h1 = mix_h1(h1, mix_k1(k1));
+            } else {
+                h1 ^= mix_k1(pair[0] as i32);
+            }
+        }
+        fmix(h1, 2 * units.len() as i32)
+    }
+
+    pub fn hash_ordered<I: Iterator<Item = i32>>(hashes: I) -> i32 {
+        let mut count = 0;
+        let mut hash = 1i32;
+        for h in hashes {
+            hash = hash.wrapping_mul(31).wrapping_add(h);
+            count += 1;
+        }
+        mix_coll_hash(hash, count)
+    }
+
+    pub fn hash_unordered<I: Iterator<Item = i32>>(hashes: I) -> i32 {
+        let mut count = 0;
+        let mut hash = 0i32;
+        for h in hashes {
+            hash = hash.wrapping_add(h);
+            count += 1;
+        }
+        mix_coll_hash(hash, count)
+    }
+
+    fn mix_coll_hash(hash: i32, count: i32) -> i32 {
+        let h1 = mix_h1(0, mix_k1(hash));
+        fmix(h1, count)
+    }
+
+    fn mix_k1(k1: i32) -> i32 {
+        k1.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2)
+    }
+
+    fn mix_h1(h1: i32, k1: i32) -> i32 {
+        (h1 ^ k1)
+            .rotate_left(13)
+            .wrapping_mul(5)
+            .wrapping_add(0xe654_6b64_u32 as i32)
+    }
+
+    fn fmix(h1: i32, length: i32) -> i32 {
+        let mut h1 = (h1 ^ length) as u32;
+        h1 ^= h1 >> 16;
+        h1 = h1.wrapping_mul(0x85eb_ca6b);
+        h1 ^= h1 >> 13;
+        h1 = h1.wrapping_mul(0xc2b2_ae35);
+        h1 ^= h1 >> 16;
+        h1 as i32
+    }
+}
@@ -0,0 +1,826 @@
+//! A lossless syntax tree, for formatters and refactoring tools.
+//!
+//! Unlike a `Value`, a `Document` keeps everything in the input: whitespace,
+//! commas, comments, values discarded with `#_` and the spelling of every
+//! number, string and character. Printing it with `Display` reproduces the
+//! input byte for byte.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::slice;
+
+use lexer::{self, Token, TokenKind};
+use parser::{Error, ErrorKind, Parser};
+use {Map, Value};
+
+/// The syntax tree of a whole input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Document {
+    pub nodes: Vec<Node>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Node {
+    /// A scalar, or whitespace, a comma or a comment.
+    Leaf(Leaf),
+    Collection(Collection),
+    /// A tag, followed by any whitespace and discarded values and then the
+    /// value it applies to.
+    Tagged(Prefixed),
+    /// `#_`, followed by any whitespace and the value it discards.
+    Discard(Prefixed),
+    /// A token that isn't valid where it is, only in documents read with
+    /// `Document::parse_recovering`.
+    Error(Leaf),
+}
+
+/// A single token and its text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Leaf {
+    pub kind: TokenKind,
+    pub text: String,
+}
+
+/// A list, vector, map or set, depending on the kind of `open`.
+///
+/// `close` is only `None` in documents read with `Document::parse_recovering`,
+/// for collections that aren't closed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Collection {
+    pub open: Leaf,
+    pub children: Vec<Node>,
+    pub close: Option<Leaf>,
+}
+
+/// A tag or `#_` and the nodes up to and including the value after it, which
+/// is only missing in documents read with `Document::parse_recovering`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Prefixed {
+    pub prefix: Leaf,
+    pub children: Vec<Node>,
+}
+
+impl Document {
+    /// Parses `str`, failing with the same errors `Parser::read` would.
+    ///
+    /// Like `Parser`, it fails with an `ErrorKind::LimitExceeded` error for
+    /// values nested more than 1024 deep, the default `max_depth`, but it
+    /// also counts the values `#_` discards as nested.
+    pub fn parse(str: &str) -> Result<Document, Error> {
+        Builder::new(str, false).document()
+    }
+
+    /// Parses `str`, returning every error in it along with a tree of as much
+    /// of it as could be read, e.g. for linters and editors.
+    ///
+    /// Tokens that aren't valid where they are become `Node::Error`s. A
+    /// closing delimiter that closes an enclosing collection also closes any
+    /// collections inside it that weren't closed, and collections are closed
+    /// at the end of the input. The first error is the one `parse` returns,
+    /// and the tree still prints back as `str`.
+    pub fn parse_recovering(str: &str) -> (Document, Vec<Error>) {
+        let mut builder = Builder::new(str, true);
+        let document = builder.document().unwrap();
+        (document, builder.errors)
+    }
+
+    /// Lowers the top-level values to `Value`s.
+    pub fn values(&self) -> Vec<Value> {
+        self.nodes.iter().filter_map(Node::to_value).collect()
+    }
+
+    /// Returns the node at `path` in the first top-level value.
+    ///
+    /// Keys in `path` look up map entries and integers index into vectors and
+    /// lists. Tags are looked through, so `[:a]` finds `:a` in `#x {:a 1}`.
+    pub fn get_in(&self, path: &[Value]) -> Option<&Node> {
+        let mut node = self.nodes.iter().find(|node| node.is_value())?;
+        for key in path {
+            node = match *node.untagged()? {
+                Node::Collection(ref collection) => &collection.children[collection.find(key)?],
+                _ => return None,
+            };
+        }
+        Some(node)
+    }
+
+    /// Sets the value at `path` in the first top-level value, like Clojure's
+    /// `assoc-in`.
+    ///
+    /// Only the edited node is changed. Missing map entries are added after
+    /// the last entry, indented like it, and missing maps along `path` are
+    /// created. An index one past the end of a vector appends to it.
+    ///
+    /// Maps along `path` whose last key has no value, which
+    /// `Document::parse_recovering` can return, can't be edited.
+    pub fn assoc_in(&mut self, path: &[Value], value: Value) -> Result<(), PathError> {
+        let node = self.root_mut()?;
+        assoc_in(node, path, 0, value)
+    }
+
+    /// Replaces the value at `path` with `f` of it, or of `nil` if there is
+    /// none, like Clojure's `update-in`.
+    pub fn update_in<F>(&mut self, path: &[Value], f: F) -> Result<(), PathError>
+    where
+        F: FnOnce(Value) -> Value,
+    {
+        let value = self
+            .get_in(path)
+            .and_then(Node::to_value)
+            .unwrap_or(Value::Nil);
+        self.assoc_in(path, f(value))
+    }
+
+    /// Removes the map entry at `path` from the first top-level value, along
+    /// with the whitespace that separated it from its neighbours.
+    ///
+    /// Does nothing if there is no such entry. Like `assoc_in`, it fails on
+    /// maps whose last key has no value.
+    pub fn dissoc(&mut self, path: &[Value]) -> Result<(), PathError> {
+        let (key, parent) = match path.split_last() {
+            Some(split) => split,
+            None => return Err(PathError::new(0, "expected a key to remove")),
+        };
+        let mut node = self.root_mut()?;
+        for (depth, key) in parent.iter().enumerate() {
+            node = match *node.untagged_mut(depth)? {
+                Node::Collection(ref mut collection) => {
+                    collection.check_entries(depth)?;
+                    match collection.find(key) {
+                        Some(index) => &mut collection.children[index],
+                        None => return Ok(()),
+                    }
+                }
+                _ => return Err(PathError::new(depth, "expected a map or vector")),
+            };
+        }
+        match *node.untagged_mut(parent.len())? {
+            Node::Collection(ref mut collection)
+                if collection.open.kind == TokenKind::LeftBrace =>
+            {
+                collection.check_entries(parent.len())?;
+                collection.remove_entry(key);
+                Ok(())
+            }
+            _ => Err(PathError::new(parent.len(), "expected a map")),
+        }
+    }
+
+    fn root_mut(&mut self) -> Result<&mut Node, PathError> {
+        self.nodes
+            .iter_mut()
+            .find(|node| node.is_value())
+            .ok_or_else(|| PathError::new(0, "the document has no value"))
+    }
+}
+
+/// Why an edit failed, with the index in the path of the key it failed at.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathError {
+    pub depth: usize,
+    pub message: String,
+}
+
+impl PathError {
+    fn new(depth: usize, message: &str) -> PathError {
+        PathError {
+            depth,
+            message: message.into(),
+        }
+    }
+}
+
+fn assoc_in(node: &mut Node, path: &[Value], depth: usize, value: Value) -> Result<(), PathError> {
+    let (key, rest) = match path.split_first() {
+        Some(split) => split,
+        None => {
+            *node = to_node(&value, depth)?;
+            return Ok(());
+        }
+    };
+    let collection = match *node.untagged_mut(depth)? {
+        Node::Collection(ref mut collection)
+            if collection.open.kind == TokenKind::LeftBrace
+                || collection.open.kind == TokenKind::LeftBracket =>
+        {
+            collection
+        }
+        _ => return Err(PathError::new(depth, "expected a map or vector")),
+    };
+    collection.check_entries(depth)?;
+    if let Some(index) = collection.find(key) {
+        return assoc_in(&mut collection.children[index], rest, depth + 1, value);
+    }
+
+    // Everything after `key` is missing, so it's added as nested maps.
+    let value = rest.iter().rev().fold(value, |value, key| {
+        let mut map = Map::new();
+        map.insert(key.clone(), value);
+        Value::Map(map)
+    });
+    if collection.open.kind == TokenKind::LeftBrace {
+        let entry = vec![to_node(key, depth)?, space(), to_node(&value, depth)?];
+        collection.append(entry, 2);
+    } else if *key == Value::Integer(collection.items().len() as i64) {
+        let item = vec![to_node(&value, depth)?];
+        collection.append(item, 1);
+    } else {
+        return Err(PathError::new(depth, "expected an index into the vector"));
+    }
+    Ok(())
+}
+
+// The node `value` is printed as, which must read back as `value` alone,
+// unlike e.g. the symbols `x y` and `1`.
+fn to_node(value: &Value, depth: usize) -> Result<Node, PathError> {
+    let printed = value.to_string();
+    if let Ok(mut document) = Document::parse(&printed) {
+        if document.nodes.len() == 1 && document.nodes[0].to_value().as_ref() == Some(value) {
+            return Ok(document.nodes.remove(0));
+        }
+    }
+    Err(PathError {
+        depth,
+        message: format!("`{}` can't be read back as EDN", printed),
+    })
+}
+
+fn space() -> Node {
+    Node::Leaf(Leaf {
+        kind: TokenKind::Whitespace,
+        text: " ".into(),
+    })
+}
+
+impl Node {
+    /// Whether this is whitespace, a comma, a comment or a discarded value,
+    /// which `to_value` skips.
+    pub fn is_trivia(&self) -> bool {
+        match *self {
+            Node::Leaf(ref leaf) => matches!(
+                leaf.kind,
+                TokenKind::Whitespace | TokenKind::Comma | TokenKind::Comment
+            ),
+            Node::Discard(_) => true,
+            Node::Collection(_) | Node::Tagged(_) | Node::Error(_) => false,
+        }
+    }
+
+    /// Whether this node is a value, i.e. neither trivia nor an error.
+    pub fn is_value(&self) -> bool {
+        match *self {
+            Node::Error(_) => false,
+            _ => !self.is_trivia(),
+        }
+    }
+
+    // The value a tag applies to, through any number of tags, or `None` for
+    // a tag without one, which `Document::parse_recovering` can return.
+    fn untagged(&self) -> Option<&Node> {
+        match *self {
+            Node::Tagged(ref tagged) => match tagged.children.last() {
+                Some(child) if child.is_value() => child.untagged(),
+                _ => None,
+            },
+            _ => Some(self),
+        }
+    }
+
+    fn untagged_mut(&mut self, depth: usize) -> Result<&mut Node, PathError> {
+        match *self {
+            Node::Tagged(ref mut tagged) => match tagged.children.last_mut() {
+                Some(child) if child.is_value() => child.untagged_mut(depth),
+                _ => Err(PathError::new(depth, "the tag has no value")),
+            },
+            _ => Ok(self),
+        }
+    }
+
+    fn is_space(&self) -> bool {
+        match *self {
+            Node::Leaf(ref leaf) => {
+                leaf.kind == TokenKind::Whitespace || leaf.kind == TokenKind::Comma
+            }
+            _ => false,
+        }
+    }
+
+    fn is_comment(&self) -> bool {
+        match *self {
+            Node::Leaf(ref leaf) => leaf.kind == TokenKind::Comment,
+            _ => false,
+        }
+    }
+
+    /// Lowers this node to the `Value` it stands for, or `None` if it isn't a
+    /// value.
+    ///
+    /// In documents read with `Document::parse_recovering`, errors are left
+    /// out, as are a map's last key if it has no value and a tag without one.
+    pub fn to_value(&self) -> Option<Value> {
+        if !self.is_value() {
+            return None;
+        }
+        // The collections and tags being lowered, with the children left to
+        // lower and the values of those lowered so far.
+        let mut stack: Vec<(&Node, slice::Iter<Node>, Vec<Value>)> = vec![];
+        let mut node = self;
+        loop {
+            let mut value = match *node {
+                Node::Leaf(ref leaf) => match Parser::new(&leaf.text).read() {
+                    Some(Ok(value)) => Some(value),
+                    _ => unreachable!("leaves are valid scalars"),
+                },
+                Node::Collection(Collection { ref children, .. })
+                | Node::Tagged(Prefixed { ref children, .. }) => {
+                    stack.push((node, children.iter(), vec![]));
+                    None
+                }
+                Node::Discard(_) | Node::Error(_) => unreachable!(),
+            };
+            // Completes the collections and tags that have no more children
+            // to lower, until one has.
+            loop {
+                let child = match stack.last_mut() {
+                    None => return value,
+                    Some(&mut (parent, ref mut children, ref mut values)) => {
+                        values.extend(value.take());
+                        match *parent {
+                            Node::Tagged(_) if !values.is_empty() => None,
+                            _ => children.find(|child| child.is_value()),
+                        }
+                    }
+                };
+                if let Some(child) = child {
+                    node = child;
+                    break;
+                }
+                let (parent, _, values) = stack.pop().unwrap();
+                value = lower(parent, values);
+            }
+        }
+    }
+}
+
+// The value of a collection or tag whose children are `values`.
+fn lower(node: &Node, values: Vec<Value>) -> Option<Value> {
+    Some(match *node {
+        Node::Collection(ref collection) => {
+            let mut items = values.into_iter();
+            match collection.open.kind {
+                TokenKind::LeftParen => Value::List(items.collect()),
+                TokenKind::LeftBracket => Value::Vector(items.collect()),
+                TokenKind::HashBrace => Value::Set(items.collect()),
+                _ => {
+                    let mut map = Map::new();
+                    while let (Some(key), Some(value)) = (items.next(), items.next()) {
+                        map.insert(key, value);
+                    }
+                    Value::Map(map)
+                }
+            }
+        }
+        Node::Tagged(ref tagged) => {
+            let value = values.into_iter().next()?;
+            Value::Tagged(tagged.prefix.text[1..].into(), Box::new(value))
+        }
+        _ => unreachable!(),
+    })
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for node in &self.nodes {
+            write!(f, "{}", node)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Node::Leaf(ref leaf) | Node::Error(ref leaf) => f.write_str(&leaf.text),
+            Node::Collection(ref collection) => {
+                f.write_str(&collection.open.text)?;
+                for node in &collection.children {
+                    write!(f, "{}", node)?;
+                }
+                match collection.close {
+                    Some(ref close) => f.write_str(&close.text),
+                    None => Ok(()),
+                }
+            }
+            Node::Tagged(ref prefixed) | Node::Discard(ref prefixed) => {
+                f.write_str(&prefixed.prefix.text)?;
+                for node in &prefixed.children {
+                    write!(f, "{}", node)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Collection {
+    // The indices of the children that aren't trivia.
+    fn items(&self) -> Vec<usize> {
+        (0..self.children.len())
+            .filter(|&i| self.children[i].is_value())
+            .collect()
+    }
+
+    // Fails if this is a map with a key that has no value, as can be read by
+    // `Document::parse_recovering`, which an edit can't tell where to put an
+    // entry in.
+    fn check_entries(&self, depth: usize) -> Result<(), PathError> {
+        if self.open.kind == TokenKind::LeftBrace && self.items().len() % 2 == 1 {
+            return Err(PathError::new(depth, "the map has a key without a value"));
+        }
+        Ok(())
+    }
+
+    // The index of the child a map's `key` or a vector or list index refers to.
+    // A map's last key is skipped if it has no value.
+    fn find(&self, key: &Value) -> Option<usize> {
+        let items = self.items();
+        match self.open.kind {
+            TokenKind::LeftBrace => items
+                .chunks_exact(2)
+                .find(|entry| self.children[entry[0]].to_value().as_ref() == Some(key))
+                .map(|entry| entry[1]),
+            TokenKind::LeftParen | TokenKind::LeftBracket => match *key {
+                Value::Integer(i) if i >= 0 => items.get(i as usize).cloned(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // Adds `nodes`, an item or an entry of `len` items, after the last item,
+    // separated from it like the last item is from the one before.
+    fn append(&mut self, nodes: Vec<Node>, len: usize) {
+        let items = self.items();
+        let (at, separator) = match items.last() {
+            Some(&last) => {
+                let first = items[items.len() - len];
+                let separator = match first.checked_sub(1).map(|i| &self.children[i]) {
+                    Some(node) if node.is_space() => node.clone(),
+                    _ => space(),
+                };
+                (last + 1, Some(separator))
+            }
+            None => (0, None),
+        };
+        let nodes = separator.into_iter().chain(nodes);
+        self.children.splice(at..at, nodes);
+    }
+
+    // Removes the entry for `key` from a map.
+    fn remove_entry(&mut self, key: &Value) {
+        let items = self.items();
+        let entry = match items
+            .chunks_exact(2)
+            .position(|entry| self.children[entry[0]].to_value().as_ref() == Some(key))
+        {
+            Some(entry) => entry,
+            None => return,
+        };
+        let mut lo = items[entry * 2];
+        let mut hi = items[entry * 2 + 1] + 1;
+        if entry * 2 + 2 < items.len() {
+            // The next entry takes this one's place.
+            while self.children[hi].is_space() {
+                hi += 1;
+            }
+        } else {
+            // The last entry takes the space before this one, except for the
+            // line break that ends a comment.
+            let key = lo;
+            while lo > 0 && self.children[lo - 1].is_space() {
+                lo -= 1;
+            }
+            if lo > 0 && self.children[lo - 1].is_comment() && lo < key {
+                lo += 1;
+            }
+        }
+        self.children.drain(lo..hi);
+    }
+}
+
+// Reads nodes from every token of the input. Errors are made by `parser`, so
+// that they match the ones `Parser::read` returns, and collected in `errors`
+// instead of being returned if `recover` is set.
+//
+// Nesting is read with a stack of frames rather than by recursion, like
+// `Parser` does, and limited to the parser's `max_depth`. Unlike `Parser`,
+// `#_` counts towards the depth too, since it nests the value it discards.
+struct Builder<'a> {
+    parser: Parser<'a>,
+    str: &'a str,
+    pos: usize,
+    recover: bool,
+    errors: Vec<Error>,
+    // The closing delimiters of the collections being read.
+    closers: Vec<TokenKind>,
+}
+
+// A collection, tag or `#_` being read and the nodes read in it so far.
+enum Frame {
+    Collection {
+        open: Token,
+        close: TokenKind,
+        children: Vec<Node>,
+        // The spans of the children that are values.
+        spans: Vec<(usize, usize)>,
+    },
+    Prefixed {
+        prefix: Token,
+        children: Vec<Node>,
+    },
+}
+
+impl<'a> Builder<'a> {
+    fn new(str: &'a str, recover: bool) -> Builder<'a> {
+        Builder {
+            parser: Parser::new(str),
+            str,
+            pos: 0,
+            recover,
+            errors: vec![],
+            closers: vec![],
+        }
+    }
+
+    fn document(&mut self) -> Result<Document, Error> {
+        let mut nodes = vec![];
+        let mut stack = vec![];
+        loop {
+            let token = self.peek();
+            // The node that `token` completes, and where it starts.
+            let (mut node, mut lo) = match (stack.last(), token) {
+                (None, None) => break,
+                (Some(&Frame::Collection { close, .. }), Some(token)) if token.kind == close => {
+                    self.next();
+                    let close = self.leaf(token);
+                    self.pop(&mut stack, Some(close))?
+                }
+                // The delimiter of an enclosing collection is left for it.
+                (Some(_), Some(token)) if self.closes(token) => {
+                    let err = self.parser.error(token);
+                    self.fail(err)?;
+                    self.pop(&mut stack, None)?
+                }
+                (Some(frame), None) => {
+                    let err = match *frame {
+                        Frame::Collection { open, .. } => self.parser.unclosed(open.lo, open.hi),
+                        Frame::Prefixed { prefix, .. } if prefix.kind == TokenKind::Tag => self
+                            .parser
+                            .missing(prefix.lo + 1, ErrorKind::MissingTaggedValue),
+                        Frame::Prefixed { prefix, .. } => self
+                            .parser
+                            .missing(prefix.lo, ErrorKind::MissingDiscardedValue),
+                    };
+                    self.fail(err)?;
+                    self.pop(&mut stack, None)?
+                }
+                (_, Some(token)) => {
+                    self.next();
+                    match self.node(token, &mut stack)? {
+                        Some(node) => (node, token.lo),
+                        None => continue,
+                    }
+                }
+            };
+            // The node is then a child of the frame it's in, completing it if
+            // that's a tag or `#_` and the node is a value.
+            loop {
+                match stack.last_mut() {
+                    None => {
+                        nodes.push(node);
+                        break;
+                    }
+                    Some(&mut Frame::Collection {
+                        ref mut children,
+                        ref mut spans,
+                        ..
+                    }) => {
+                        if node.is_value() {
+                            spans.push((lo, self.pos));
+                        }
+                        children.push(node);
+                        break;
+                    }
+                    Some(&mut Frame::Prefixed {
+                        ref mut children, ..
+                    }) => {
+                        let done = node.is_value();
+                        children.push(node);
+                        if !done {
+                            break;
+                        }
+                    }
+                }
+                let (prefixed, prefix_lo) = self.pop(&mut stack, None)?;
+                node = prefixed;
+                lo = prefix_lo;
+            }
+        }
+        Ok(Document { nodes })
+    }
+
+    fn peek(&self) -> Option<Token> {
+        if self.pos == self.str.len() {
+            return None;
+        }
+        Some(lexer::lex(self.str, self.pos))
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek()?;
+        self.pos = token.hi;
+        Some(token)
+    }
+
+    fn leaf(&self, token: Token) -> Leaf {
+        Leaf {
+            kind: token.kind,
+            text: self.str[token.lo..token.hi].into(),
+        }
+    }
+
+    // Returns `err`, or records it if recovering.
+    fn fail(&mut self, err: Error) -> Result<(), Error> {
+        if self.recover {
+            self.errors.push(err);
+            Ok(())
+        } else {
+            Err(err)
+        }
+    }
+
+    // Whether `token` closes a collection being read.
+    fn closes(&self, token: Token) -> bool {
+        self.closers.contains(&token.kind)
+    }
+
+    // Reads the node that starts with `token`, or pushes a frame for it if it
+    // starts a collection, tag or `#_`.
+    fn node(&mut self, token: Token, stack: &mut Vec<Frame>) -> Result<Option<Node>, Error> {
+        let close = match token.kind {
+            TokenKind::LeftParen => Some(TokenKind::RightParen),
+            TokenKind::LeftBracket => Some(TokenKind::RightBracket),
+            TokenKind::LeftBrace | TokenKind::HashBrace => Some(TokenKind::RightBrace),
+            TokenKind::Tag | TokenKind::Discard => None,
+            TokenKind::Whitespace | TokenKind::Comma | TokenKind::Comment => {
+                return Ok(Some(Node::Leaf(self.leaf(token))));
+            }
+            // Reading the scalar checks it, e.g. that an integer fits, and
+            // reports anything else, like a closing delimiter, as an error.
+            _ => {
+                return match self.parser.borrowed_scalar(token) {
+                    Ok(_) => Ok(Some(Node::Leaf(self.leaf(token)))),
+                    Err(err) => {
+                        self.fail(err)?;
+                        Ok(Some(Node::Error(self.leaf(token))))
+                    }
+                };
+            }
+        };
+        if let Err(err) = self.parser.nest(stack.len(), token) {
+            self.fail(err)?;
+            return Ok(Some(Node::Error(self.too_deep(token))));
+        }
+        stack.push(match close {
+            Some(close) => {
+                self.closers.push(close);
+                Frame::Collection {
+                    open: token,
+                    close,
+                    children: vec![],
+                    spans: vec![],
+                }
+            }
+            None => Frame::Prefixed {
+                prefix: token,
+                children: vec![],
+            },
+        });
+        Ok(None)
+    }
+
+    // When recovering, the text of a collection, tag or `#_` nested too
+    // deeply, up to the end of the value it reads or the input.
+    fn too_deep(&mut self, token: Token) -> Leaf {
+        // The depth inside the text, and how many values it still needs at
+        // the depth it starts at.
+        let (mut depth, mut needed) = match token.kind {
+            TokenKind::Tag | TokenKind::Discard => (0, 1),
+            _ => (1, 1),
+        };
+        let mut hi = token.hi;
+        while needed > 0 {
+            let token = match self.peek() {
+                Some(token) => token,
+                None => break,
+            };
+            match token.kind {
+                TokenKind::RightParen | TokenKind::RightBracket | TokenKind::RightBrace
+                    if depth == 0 =>
+                {
+                    break
+                }
+                TokenKind::RightParen | TokenKind::RightBracket | TokenKind::RightBrace => {
+                    depth -= 1;
+                    if depth == 0 {
+                        needed -= 1;
+                    }
+                }
+                TokenKind::LeftParen
+                | TokenKind::LeftBracket
+                | TokenKind::LeftBrace
+                | TokenKind::HashBrace => depth += 1,
+                TokenKind::Discard if depth == 0 => needed += 1,
+                TokenKind::Whitespace
+                | TokenKind::Comma
+                | TokenKind::Comment
+                | TokenKind::Tag
+                | TokenKind::Discard
+                | TokenKind::Error => {}
+                _ if depth == 0 => needed -= 1,
+                _ => {}
+            }
+            self.next();
+            hi = token.hi;
+        }
+        self.leaf(Token {
+            kind: TokenKind::Error,
+            lo: token.lo,
+            hi,
+        })
+    }
+
+    // Pops the innermost frame, ending with `close` if it's a collection that
+    // was closed, and returns its node and where it starts.
+    fn pop(&mut self, stack: &mut Vec<Frame>, close: Option<Leaf>) -> Result<(Node, usize), Error> {
+        match stack.pop().unwrap() {
+            Frame::Collection {
+                open,
+                children,
+                spans,
+                ..
+            } => {
+                self.closers.pop();
+                if close.is_some() {
+                    if open.kind == TokenKind::LeftBrace && spans.len() % 2 == 1 {
+                        self.parser.skip_to(self.pos);
+                        let err = self.parser.odd_map(open.lo);
+                        self.fail(err)?;
+                    } else if let Some(err) = self.duplicate(open, &children, &spans) {
+                        self.fail(err)?;
+                    }
+                }
+                let collection = Collection {
+                    open: self.leaf(open),
+                    children,
+                    close,
+                };
+                Ok((Node::Collection(collection), open.lo))
+            }
+            Frame::Prefixed { prefix, children } => {
+                let prefixed = Prefixed {
+                    prefix: self.leaf(prefix),
+                    children,
+                };
+                let node = match prefix.kind {
+                    TokenKind::Tag => Node::Tagged(prefixed),
+                    _ => Node::Discard(prefixed),
+                };
+                Ok((node, prefix.lo))
+            }
+        }
+    }
+
+    // The error for the first repeated key or element of the map or set opened
+    // by `open`, whose values span `spans`.
+    fn duplicate(&self, open: Token, children: &[Node], spans: &[(usize, usize)]) -> Option<Error> {
+        let step = match open.kind {
+            TokenKind::LeftBrace => 2,
+            TokenKind::HashBrace => 1,
+            _ => return None,
+        };
+        let mut seen = HashMap::new();
+        let values = children.iter().filter(|node| node.is_value());
+        for (node, &(lo, hi)) in values.zip(spans).step_by(step) {
+            let value = match node.to_value() {
+                Some(value) => value,
+                None => continue,
+            };
+            if let Some(&(first_lo, first_hi)) = seen.get(&value) {
+                return Some(self.parser.repeated(open, lo, hi, first_lo, first_hi));
+            }
+            seen.insert(value, (lo, hi));
+        }
+        None
+    }
+}
@@ -0,0 +1,204 @@
+//! Reports of parse errors like rustc's, showing the lines of the input an
+//! error is about with its span underlined.
+
+use std::fmt;
+
+use lexer;
+use parser::{Error, ErrorKind, Position};
+
+/// An `Error` rendered with `Display` as a report on `source`, the input it
+/// was returned for:
+///
+/// ```text
+/// error: unclosed `{`
+///  --> config.edn:1:1
+///   |
+/// 1 | {:port 80
+///   | ^ unclosed delimiter
+/// 2 |  :host "example.com"
+///   |                     - the input ends here
+/// ```
+///
+/// If the error's span isn't in `source`, only the first two lines are
+/// rendered.
+pub struct Diagnostic<'a> {
+    error: &'a Error,
+    source: &'a str,
+    color: bool,
+}
+
+// A span on a single line to underline, with `^` if it's the primary one and
+// `-` otherwise.
+struct Label {
+    line: usize,
+    column: usize,
+    width: usize,
+    text: &'static str,
+    primary: bool,
+}
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+impl<'a> Diagnostic<'a> {
+    pub fn new(error: &'a Error, source: &'a str) -> Diagnostic<'a> {
+        Diagnostic {
+            error,
+            source,
+            color: false,
+        }
+    }
+
+    /// Whether to colour the report with ANSI escape codes, which it isn't by
+    /// default.
+    pub fn with_color(mut self, color: bool) -> Diagnostic<'a> {
+        self.color = color;
+        self
+    }
+
+    fn labels(&self) -> Option<Vec<Label>> {
+        let error = self.error;
+        let source = self.source;
+        if error.hi > source.len()
+            || !source.is_char_boundary(error.lo)
+            || !source.is_char_boundary(error.hi)
+        {
+            return None;
+        }
+
+        let at_end = match error.kind {
+            ErrorKind::UnclosedDelimiter { .. } => Some("unclosed delimiter"),
+            ErrorKind::UnclosedString => Some("unclosed string"),
+            ErrorKind::MissingTaggedValue | ErrorKind::MissingDiscardedValue => {
+                Some("expected a value after this")
+            }
+            _ => None,
+        };
+        Some(match at_end {
+            // Errors spanning up to the end of the input point at the token
+            // they start with and at the end.
+            Some(text) if error.lo < source.len() => {
+                let token = lexer::lex(source, error.lo);
+                let hi = match error.kind {
+                    ErrorKind::UnclosedString => lexer::char_end(source, error.lo),
+                    _ => token.hi,
+                };
+                // Trailing whitespace is left out so as not to point at an
+                // empty line.
+                let end = source.trim_end().len().max(hi);
+                vec![
+                    self.label(error.lo, hi, text, true),
+                    self.label(end, end, "the input ends here", false),
+                ]
+            }
+            _ => match error.kind {
+                ErrorKind::DuplicateKey {
+                    first_lo, first_hi, ..
+                }
+                | ErrorKind::DuplicateElement {
+                    first_lo, first_hi, ..
+                } => vec![
+                    self.label(first_lo, first_hi, "first used here", false),
+                    self.label(error.lo, error.hi, "used again here", true),
+                ],
+                _ => vec![self.label(error.lo, error.hi, "", true)],
+            },
+        })
+    }
+
+    // The label for `lo..hi`, cut off at the end of its first line.
+    fn label(&self, lo: usize, hi: usize, text: &'static str, primary: bool) -> Label {
+        let mut start = Position::start();
+        start.advance(&self.source.as_bytes()[..lo]);
+        let line_end = self.source[lo..]
+            .find('\n')
+            .map_or(self.source.len(), |i| lo + i);
+        let width = self.source[lo..hi.min(line_end)].chars().count();
+        Label {
+            line: start.line,
+            column: start.column,
+            width: width.max(1),
+            text,
+            primary,
+        }
+    }
+
+    fn paint(&self, f: &mut fmt::Formatter, color: &str, text: &str) -> fmt::Result {
+        if self.color {
+            write!(f, "{}{}{}", color, text, RESET)
+        } else {
+            f.write_str(text)
+        }
+    }
+}
+
+impl<'a> fmt::Display for Diagnostic<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let error = self.error;
+        self.paint(f, RED, "error")?;
+        self.paint(f, BOLD, &format!(": {}", error.kind))?;
+        writeln!(f)?;
+
+        let labels = self.labels().unwrap_or_default();
+        let last_line = labels.iter().map(|label| label.line).max().unwrap_or(0);
+        let gutter = last_line.max(error.start.line).to_string().len();
+        let location = match error.source_name {
+            Some(ref name) => format!("{}:{}:{}", name, error.start.line, error.start.column),
+            None => format!("{}:{}", error.start.line, error.start.column),
+        };
+        write!(f, "{:1$}", "", gutter)?;
+        self.paint(f, BLUE, "-->")?;
+        writeln!(f, " {}", location)?;
+        if labels.is_empty() {
+            return Ok(());
+        }
+
+        let lines: Vec<&str> = self.source.split('\n').collect();
+        let empty = format!("{:1$} |", "", gutter);
+        self.paint(f, BLUE, &empty)?;
+        writeln!(f)?;
+        let mut previous: Option<usize> = None;
+        for label in &labels {
+            let line = lines[label.line - 1].trim_end_matches('\r');
+            match previous {
+                Some(previous) if previous == label.line => {}
+                Some(previous) if previous + 1 < label.line => {
+                    self.paint(f, BLUE, "...")?;
+                    writeln!(f)?;
+                }
+                _ => {}
+            }
+            if previous != Some(label.line) {
+                self.paint(f, BLUE, &format!("{:1$} |", label.line, gutter))?;
+                writeln!(f, " {}", line)?;
+            }
+            previous = Some(label.line);
+
+            self.paint(f, BLUE, &empty)?;
+            // Tabs are kept so that the underline lines up however wide they
+            // are shown.
+            let indent: String = line
+                .chars()
+                .chain(std::iter::repeat(' '))
+                .take(label.column - 1)
+                .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+                .collect();
+            write!(f, " {}", indent)?;
+            let (color, marker) = if label.primary {
+                (RED, "^")
+            } else {
+                (BLUE, "-")
+            };
+            let mut underline = marker.repeat(label.width);
+            if !label.text.is_empty() {
+                underline.push(' ');
+                underline.push_str(label.text);
+            }
+            self.paint(f, color, &underline)?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
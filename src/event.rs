@@ -0,0 +1,173 @@
+//! A pull parser that reads a document as a sequence of events instead of
+//! building a tree.
+
+use lexer::{Token, TokenKind};
+use parser::{Error, ErrorKind, Parser, ParserOptions};
+use BorrowedValue;
+
+/// What an `Event` is.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EventKind<'a> {
+    StartList,
+    StartVector,
+    StartMap,
+    StartSet,
+    /// A tag, which applies to the value whose events follow it.
+    Tag(&'a str),
+    /// A value that isn't a collection, borrowed from the input like
+    /// `Parser::read_borrowed` does.
+    Scalar(BorrowedValue<'a>),
+    /// The end of the innermost collection.
+    End,
+    /// A value discarded with `#_`, which is read but produces no events.
+    Discard,
+}
+
+/// An event and the bytes `lo..hi` of the input it was read from.
+///
+/// A start or end event spans the delimiter, a tag spans `#` and its name,
+/// and a discard spans `#_` and the discarded value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event<'a> {
+    pub kind: EventKind<'a>,
+    pub lo: usize,
+    pub hi: usize,
+}
+
+/// Reads the events of a document, an iterator over `Result<Event, Error>`.
+///
+/// Collections are checked as they are read like `Parser::read` checks them,
+/// e.g. for an odd number of items in a map, and the first error ends the
+/// events. Repeated map keys and set elements aren't, since that would mean
+/// keeping every key, so events are read as if
+/// `ParserOptions::allow_duplicates` were set.
+pub struct EventReader<'a> {
+    parser: Parser<'a>,
+    stack: Vec<Frame>,
+    done: bool,
+}
+
+enum Frame {
+    Collection {
+        open: Token,
+        close: TokenKind,
+        items: usize,
+    },
+    Tag {
+        lo: usize,
+    },
+}
+
+impl<'a> EventReader<'a> {
+    pub fn new(str: &'a str) -> EventReader<'a> {
+        EventReader {
+            parser: Parser::new(str).with_options(ParserOptions::new().allow_duplicates(true)),
+            stack: vec![],
+            done: false,
+        }
+    }
+
+    /// Returns how deeply nested in collections the next event is.
+    pub fn depth(&self) -> usize {
+        self.stack
+            .iter()
+            .filter(|frame| matches!(frame, Frame::Collection { .. }))
+            .count()
+    }
+
+    fn event(&mut self) -> Option<Result<Event<'a>, Error>> {
+        let token = match self.parser.significant() {
+            Some(token) => token,
+            None => return self.end_of_input().map(Err),
+        };
+        let kind = match token.kind {
+            TokenKind::LeftParen => self.open(token, TokenKind::RightParen, EventKind::StartList),
+            TokenKind::LeftBracket => {
+                self.open(token, TokenKind::RightBracket, EventKind::StartVector)
+            }
+            TokenKind::LeftBrace => self.open(token, TokenKind::RightBrace, EventKind::StartMap),
+            TokenKind::HashBrace => self.open(token, TokenKind::RightBrace, EventKind::StartSet),
+            TokenKind::RightParen | TokenKind::RightBracket | TokenKind::RightBrace => {
+                match self.close(token) {
+                    Ok(()) => EventKind::End,
+                    Err(err) => return Some(Err(err)),
+                }
+            }
+            TokenKind::Discard => match self.parser.discard(token.lo) {
+                Ok(()) => EventKind::Discard,
+                Err(err) => return Some(Err(err)),
+            },
+            TokenKind::Tag => {
+                self.stack.push(Frame::Tag { lo: token.lo + 1 });
+                EventKind::Tag(&self.parser.text(token)[1..])
+            }
+            _ => match self.parser.borrowed_scalar(token) {
+                Ok(value) => {
+                    self.end_value();
+                    EventKind::Scalar(value)
+                }
+                Err(err) => return Some(Err(err)),
+            },
+        };
+        Some(Ok(Event {
+            kind,
+            lo: token.lo,
+            hi: self.parser.position(),
+        }))
+    }
+
+    fn open(&mut self, open: Token, close: TokenKind, kind: EventKind<'a>) -> EventKind<'a> {
+        self.stack.push(Frame::Collection {
+            open,
+            close,
+            items: 0,
+        });
+        kind
+    }
+
+    fn close(&mut self, token: Token) -> Result<(), Error> {
+        match self.stack.last() {
+            Some(&Frame::Collection { open, close, items }) if close == token.kind => {
+                self.stack.pop();
+                if open.kind == TokenKind::LeftBrace && items % 2 == 1 {
+                    return Err(self.parser.odd_map(open.lo));
+                }
+                self.end_value();
+                Ok(())
+            }
+            _ => Err(self.parser.error(token)),
+        }
+    }
+
+    // Called after each value, which completes any tags before it.
+    fn end_value(&mut self) {
+        while let Some(&Frame::Tag { .. }) = self.stack.last() {
+            self.stack.pop();
+        }
+        if let Some(&mut Frame::Collection { ref mut items, .. }) = self.stack.last_mut() {
+            *items += 1;
+        }
+    }
+
+    fn end_of_input(&mut self) -> Option<Error> {
+        match self.stack.pop()? {
+            Frame::Collection { open, .. } => Some(self.parser.unclosed(open.lo, open.hi)),
+            Frame::Tag { lo } => Some(self.parser.missing(lo, ErrorKind::MissingTaggedValue)),
+        }
+    }
+}
+
+impl<'a> Iterator for EventReader<'a> {
+    type Item = Result<Event<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let event = self.event();
+        if let Some(Err(_)) = event {
+            self.done = true;
+        }
+        event
+    }
+}
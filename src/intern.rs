@@ -0,0 +1,136 @@
+//! Interned keyword and symbol names, enabled by the `intern` feature.
+//!
+//! Every `Parser` owns an `Interner`, so each distinct keyword or symbol it
+//! reads is allocated once and shared by all its occurrences. Comparing two
+//! names from the same interner only compares pointers.
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// A cheaply clonable, immutable string shared between equal names.
+#[derive(Clone)]
+pub struct Interned(Arc<str>);
+
+impl Interned {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Interned {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Interned {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Interned {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Interned {
+    fn eq(&self, other: &Interned) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+impl Eq for Interned {}
+
+impl<'a> PartialEq<&'a str> for Interned {
+    fn eq(&self, other: &&'a str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialOrd for Interned {
+    fn partial_cmp(&self, other: &Interned) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Interned {
+    fn cmp(&self, other: &Interned) -> Ordering {
+        if Arc::ptr_eq(&self.0, &other.0) {
+            Ordering::Equal
+        } else {
+            self.0.cmp(&other.0)
+        }
+    }
+}
+
+impl Hash for Interned {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl fmt::Debug for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<'a> From<&'a str> for Interned {
+    fn from(s: &'a str) -> Self {
+        Interned(s.into())
+    }
+}
+
+impl From<String> for Interned {
+    fn from(s: String) -> Self {
+        Interned(s.into())
+    }
+}
+
+/// A set of interned names.
+///
+/// A parser's interner can be taken out with `Parser::into_interner` and
+/// passed to `Parser::with_interner` to share names across documents.
+#[derive(Clone, Debug, Default)]
+pub struct Interner {
+    names: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Interner {
+        Interner::default()
+    }
+
+    /// Returns the shared copy of `name`, allocating it on first use.
+    pub fn intern(&mut self, name: &str) -> Interned {
+        if let Some(name) = self.names.get(name) {
+            return Interned(name.clone());
+        }
+        let name: Arc<str> = name.into();
+        self.names.insert(name.clone());
+        Interned(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
@@ -0,0 +1,490 @@
+//! Splitting EDN into tokens, for tools like editors that work on its text
+//! rather than its values.
+//!
+//! Every byte of the input belongs to exactly one token, including whitespace
+//! and comments, and input that isn't valid EDN becomes `Error` tokens, so
+//! lexing never stops early. `Parser` reads values from these same tokens.
+
+use memchr::{memchr, memchr2};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    Whitespace,
+    Comma,
+    /// From a `;` up to the end of the line.
+    Comment,
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    LeftBrace,
+    RightBrace,
+    /// `#{`, which opens a set.
+    HashBrace,
+    String,
+    Char,
+    Integer,
+    Float,
+    /// A ratio like `22/7`, which `Parser` only reads in Clojure number mode.
+    Ratio,
+    Symbol,
+    Keyword,
+    /// `#` and a tag's name.
+    Tag,
+    /// `#_`, which discards the value after it.
+    Discard,
+    /// An unexpected character, an invalid `#` dispatch, a string that isn't
+    /// closed or has invalid escapes, or an invalid character literal.
+    Error,
+}
+
+/// A token spanning the bytes `lo..hi` of the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub lo: usize,
+    pub hi: usize,
+}
+
+/// An iterator over the tokens of a string.
+#[derive(Clone, Debug)]
+pub struct Lexer<'a> {
+    str: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(str: &'a str) -> Lexer<'a> {
+        Lexer { str, pos: 0 }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        if self.pos == self.str.len() {
+            return None;
+        }
+        let token = lex(self.str, self.pos);
+        self.pos = token.hi;
+        Some(token)
+    }
+}
+
+// Returns the token starting at `lo`, which must be before the end of `str`.
+//
+// The input is scanned byte by byte; since every byte that has a meaning in
+// EDN is ASCII, UTF-8 only needs to be decoded in character literals and for
+// the rare non-ASCII whitespace.
+#[inline]
+pub(crate) fn lex(str: &str, lo: usize) -> Token {
+    let bytes = str.as_bytes();
+    let token = |kind, hi| Token { kind, lo, hi };
+    let byte = bytes[lo];
+    match byte {
+        b',' => token(TokenKind::Comma, lo + 1),
+        b';' => token(TokenKind::Comment, end_of_line(bytes, lo)),
+        b'(' => token(TokenKind::LeftParen, lo + 1),
+        b')' => token(TokenKind::RightParen, lo + 1),
+        b'[' => token(TokenKind::LeftBracket, lo + 1),
+        b']' => token(TokenKind::RightBracket, lo + 1),
+        b'{' => token(TokenKind::LeftBrace, lo + 1),
+        b'}' => token(TokenKind::RightBrace, lo + 1),
+        b'0'..=b'9' => number(str, lo),
+        b'+' | b'-' | b'.' => match bytes.get(lo + 1) {
+            Some(b'0'..=b'9') => number(str, lo),
+            _ => token(TokenKind::Symbol, symbol_end(str, lo + 1)),
+        },
+        b'"' => string(bytes, lo),
+        b'\\' => char_literal(str, lo),
+        // A keyword's name can't be empty.
+        b':' => match symbol_end(str, lo + 1) {
+            hi if hi > lo + 1 => token(TokenKind::Keyword, hi),
+            _ => token(TokenKind::Error, lo + 1),
+        },
+        b'/' => token(TokenKind::Symbol, symbol_end(str, lo + 1)),
+        b'#' => match bytes.get(lo + 1) {
+            Some(b'{') => token(TokenKind::HashBrace, lo + 2),
+            Some(b'_') => token(TokenKind::Discard, lo + 2),
+            _ if starts_symbol(str, lo + 1) => token(TokenKind::Tag, symbol_end(str, lo + 1)),
+            _ => token(TokenKind::Error, char_end(str, lo + 1)),
+        },
+        _ if is(byte, WHITESPACE) => token(TokenKind::Whitespace, whitespace(str, lo)),
+        _ if is(byte, SYMBOL_HEAD) => token(TokenKind::Symbol, symbol_end(str, lo + 1)),
+        _ => match whitespace(str, lo) {
+            hi if hi > lo => token(TokenKind::Whitespace, hi),
+            _ if starts_symbol(str, lo) => token(TokenKind::Symbol, symbol_end(str, lo)),
+            _ => token(TokenKind::Error, char_end(str, lo)),
+        },
+    }
+}
+
+// Returns where the next token that isn't whitespace, a comma or a comment
+// starts, without producing the tokens in between.
+#[inline]
+pub(crate) fn skip_trivia(str: &str, mut pos: usize) -> usize {
+    let bytes = str.as_bytes();
+    while let Some(&byte) = bytes.get(pos) {
+        if is(byte, WHITESPACE) || byte == b',' {
+            pos += 1;
+        } else if byte == b';' {
+            pos = end_of_line(bytes, pos);
+        } else if byte >= 0x80 {
+            match whitespace(str, pos) {
+                hi if hi > pos => pos = hi,
+                _ => return pos,
+            }
+        } else {
+            return pos;
+        }
+    }
+    pos
+}
+
+// An integer, optionally ending in `N`, or a float with a fraction, an
+// exponent or an `M` at the end, optionally preceded by a sign. Numbers run up
+// to the next delimiter, so anything else that follows, as in `1a` or `.5`,
+// makes the whole token an `Error`.
+//
+// Clojure's hexadecimal and radix integers, like `0xFF` and `2r1010`, are
+// integers too, and `22/7` a ratio. `Parser` only reads them in Clojure number
+// mode, and checks their digits then.
+fn number(str: &str, lo: usize) -> Token {
+    let bytes = str.as_bytes();
+    let mut hi = lo;
+    if let Some(b'+') | Some(b'-') = bytes.get(lo) {
+        hi += 1;
+    }
+    let digits = hi;
+    hi = advance_while(bytes, hi, DIGIT);
+    let mut kind = if hi > digits {
+        TokenKind::Integer
+    } else {
+        TokenKind::Error
+    };
+    let prefix = &bytes[digits..hi];
+    match bytes.get(hi) {
+        Some(b'x') | Some(b'X')
+            if prefix == b"0" && bytes.get(hi + 1).is_some_and(u8::is_ascii_hexdigit) =>
+        {
+            hi += 1;
+            while bytes.get(hi).is_some_and(u8::is_ascii_hexdigit) {
+                hi += 1;
+            }
+            if bytes.get(hi) == Some(&b'N') {
+                hi += 1;
+            }
+            return number_end(str, lo, hi, TokenKind::Integer);
+        }
+        Some(b'/') if !prefix.is_empty() && bytes.get(hi + 1).is_some_and(u8::is_ascii_digit) => {
+            hi = advance_while(bytes, hi + 1, DIGIT);
+            return number_end(str, lo, hi, TokenKind::Ratio);
+        }
+        Some(b'r') | Some(b'R')
+            if (1..=2).contains(&prefix.len())
+                && bytes.get(hi + 1).is_some_and(u8::is_ascii_alphanumeric) =>
+        {
+            hi += 1;
+            while bytes.get(hi).is_some_and(u8::is_ascii_alphanumeric) {
+                hi += 1;
+            }
+            return number_end(str, lo, hi, TokenKind::Integer);
+        }
+        _ => {}
+    }
+    if bytes.get(hi) == Some(&b'.') {
+        hi = advance_while(bytes, hi + 1, DIGIT);
+        kind = float(kind);
+    }
+    if let Some(b'e') | Some(b'E') = bytes.get(hi) {
+        let mut exponent = hi + 1;
+        if let Some(b'+') | Some(b'-') = bytes.get(exponent) {
+            exponent += 1;
+        }
+        let end = advance_while(bytes, exponent, DIGIT);
+        if end > exponent {
+            hi = end;
+            kind = float(kind);
+        }
+    }
+    match bytes.get(hi) {
+        Some(b'N') if kind == TokenKind::Integer => hi += 1,
+        Some(b'M') => {
+            hi += 1;
+            kind = float(kind);
+        }
+        _ => {}
+    }
+    number_end(str, lo, hi, kind)
+}
+
+// The number of kind `kind` spanning `lo..hi`, or an `Error` up to the next
+// delimiter if there's more to it.
+fn number_end(str: &str, lo: usize, hi: usize, kind: TokenKind) -> Token {
+    match symbol_end(str, hi) {
+        end if end > hi => Token {
+            kind: TokenKind::Error,
+            lo,
+            hi: end,
+        },
+        _ => Token { kind, lo, hi },
+    }
+}
+
+// The kind of a number that turns out to be a float, unless it's invalid.
+fn float(kind: TokenKind) -> TokenKind {
+    match kind {
+        TokenKind::Error => TokenKind::Error,
+        _ => TokenKind::Float,
+    }
+}
+
+// Jumps between quotes and backslashes with `memchr2`, checking escapes.
+fn string(bytes: &[u8], lo: usize) -> Token {
+    let mut kind = TokenKind::String;
+    let mut pos = lo + 1;
+    loop {
+        let at = match memchr2(b'"', b'\\', &bytes[pos..]) {
+            Some(offset) => pos + offset,
+            None => {
+                return Token {
+                    kind: TokenKind::Error,
+                    lo,
+                    hi: bytes.len(),
+                }
+            }
+        };
+        if bytes[at] == b'"' {
+            return Token {
+                kind,
+                lo,
+                hi: at + 1,
+            };
+        }
+        match bytes.get(at + 1) {
+            Some(b't') | Some(b'r') | Some(b'n') | Some(b'\\') | Some(b'"') => {}
+            _ => kind = TokenKind::Error,
+        }
+        pos = (at + 2).min(bytes.len());
+    }
+}
+
+fn char_literal(str: &str, lo: usize) -> Token {
+    // A `\` can't be followed by whitespace; only the `\` is invalid.
+    if str[lo + 1..].starts_with(char::is_whitespace) {
+        return Token {
+            kind: TokenKind::Error,
+            lo,
+            hi: lo + 1,
+        };
+    }
+    // The first character is always part of the literal, so that e.g. `\(`
+    // and `\;` work.
+    let mut hi = char_end(str, lo + 1);
+    for ch in str[hi..].chars() {
+        if ch.is_whitespace() || is_delimiter(ch) {
+            break;
+        }
+        hi += ch.len_utf8();
+    }
+    let kind = match char_name(&str[lo + 1..hi]) {
+        Some(_) => TokenKind::Char,
+        None => TokenKind::Error,
+    };
+    Token { kind, lo, hi }
+}
+
+// The character a literal stands for, given the text after its `\`.
+pub(crate) fn char_name(name: &str) -> Option<char> {
+    match name {
+        "newline" => Some('\n'),
+        "return" => Some('\r'),
+        "space" => Some(' '),
+        "tab" => Some('\t'),
+        // A UTF-16 code unit in hex, which can't be a surrogate.
+        _ if name.len() == 5 && name.starts_with('u') => {
+            let hex = &name[1..];
+            if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+                return None;
+            }
+            char::from_u32(u32::from_str_radix(hex, 16).ok()?)
+        }
+        _ => {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) => Some(ch),
+                _ => None,
+            }
+        }
+    }
+}
+
+fn end_of_line(bytes: &[u8], pos: usize) -> usize {
+    memchr(b'\n', &bytes[pos..]).map_or(bytes.len(), |offset| pos + offset)
+}
+
+// The end of the whitespace (but not commas) starting at `pos`.
+fn whitespace(str: &str, mut pos: usize) -> usize {
+    let bytes = str.as_bytes();
+    while let Some(&byte) = bytes.get(pos) {
+        if byte < 0x80 {
+            if !is(byte, WHITESPACE) {
+                break;
+            }
+            pos += 1;
+        } else {
+            match str[pos..].chars().next() {
+                Some(ch) if ch.is_whitespace() => pos += ch.len_utf8(),
+                _ => break,
+            }
+        }
+    }
+    pos
+}
+
+// The end of the character starting at `pos`, or `pos` at the end of input.
+pub(crate) fn char_end(str: &str, pos: usize) -> usize {
+    str[pos..]
+        .chars()
+        .next()
+        .map_or(pos, |ch| pos + ch.len_utf8())
+}
+
+// Whether a symbol can start with the character at `pos`.
+fn starts_symbol(str: &str, pos: usize) -> bool {
+    str[pos..].chars().next().is_some_and(is_symbol_head)
+}
+
+// The end of the symbol characters starting at `pos`. Only non-ASCII ones
+// need to be decoded.
+fn symbol_end(str: &str, mut pos: usize) -> usize {
+    let bytes = str.as_bytes();
+    while let Some(&byte) = bytes.get(pos) {
+        if byte < 0x80 {
+            if !is(byte, SYMBOL_TAIL) {
+                break;
+            }
+            pos += 1;
+        } else {
+            match str[pos..].chars().next() {
+                Some(ch) if is_symbol_tail(ch) => pos += ch.len_utf8(),
+                _ => break,
+            }
+        }
+    }
+    pos
+}
+
+fn advance_while(bytes: &[u8], mut pos: usize, class: u8) -> usize {
+    while pos < bytes.len() && is(bytes[pos], class) {
+        pos += 1;
+    }
+    pos
+}
+
+fn is_delimiter(ch: char) -> bool {
+    matches!(
+        ch,
+        '(' | ')' | '[' | ']' | '{' | '}' | '"' | ';' | ',' | '\\'
+    )
+}
+
+// Character classes of ASCII bytes, looked up in `CLASSES`. Bytes of non-ASCII
+// characters belong to no class.
+const WHITESPACE: u8 = 1;
+const DIGIT: u8 = 2;
+const SYMBOL_HEAD: u8 = 4;
+const SYMBOL_TAIL: u8 = 8;
+
+static CLASSES: [u8; 256] = classes();
+
+const fn classes() -> [u8; 256] {
+    let mut classes = [0; 256];
+    let mut byte = 0;
+    while byte < 128 {
+        let b = byte as u8;
+        let mut class = 0;
+        // `char::is_whitespace`; commas are tokens of their own.
+        if matches!(b, b' ' | b'\t' | b'\n' | 0x0b | 0x0c | b'\r') {
+            class |= WHITESPACE;
+        }
+        if b.is_ascii_digit() {
+            class |= DIGIT;
+        }
+        if b.is_ascii_alphabetic()
+            || matches!(
+                b,
+                b'.' | b'*'
+                    | b'+'
+                    | b'!'
+                    | b'-'
+                    | b'_'
+                    | b'?'
+                    | b'$'
+                    | b'%'
+                    | b'&'
+                    | b'='
+                    | b'<'
+                    | b'>'
+            )
+        {
+            class |= SYMBOL_HEAD | SYMBOL_TAIL;
+        }
+        if matches!(b, b'0'..=b'9' | b':' | b'#' | b'/') {
+            class |= SYMBOL_TAIL;
+        }
+        classes[byte] = class;
+        byte += 1;
+    }
+    classes
+}
+
+// Whether `symbol`, or a keyword's name, is one the spec allows: `/` alone, or
+// a name optionally preceded by a prefix and `/`, neither of which starts with
+// a digit, or with `+`, `-` or `.` and then a digit.
+pub(crate) fn is_valid_symbol(symbol: &str) -> bool {
+    if symbol == "/" {
+        return true;
+    }
+    match symbol.find('/') {
+        Some(at) => {
+            let name = &symbol[at + 1..];
+            is_valid_name(&symbol[..at]) && is_valid_name(name) && !name.contains('/')
+        }
+        None => is_valid_name(symbol),
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    match bytes.first() {
+        Some(b'+') | Some(b'-') | Some(b'.') => !matches!(bytes.get(1), Some(b'0'..=b'9')),
+        Some(byte) => !byte.is_ascii_digit(),
+        None => false,
+    }
+}
+
+// Whether a symbol, keyword name or tag can start with `ch`: an ASCII letter,
+// one of `. * + ! - _ ? $ % & = < >`, or any other letter.
+pub(crate) fn is_symbol_head(ch: char) -> bool {
+    if ch.is_ascii() {
+        is(ch as u8, SYMBOL_HEAD)
+    } else {
+        ch.is_alphabetic()
+    }
+}
+
+// Whether `ch` can be in a symbol after its first character: a symbol head,
+// a digit, `:`, `#` or `/`, or any other letter or number.
+pub(crate) fn is_symbol_tail(ch: char) -> bool {
+    if ch.is_ascii() {
+        is(ch as u8, SYMBOL_TAIL)
+    } else {
+        ch.is_alphanumeric()
+    }
+}
+
+fn is(byte: u8, class: u8) -> bool {
+    CLASSES[byte as usize] & class != 0
+}
@@ -1,28 +1,316 @@
+#[cfg(feature = "persistent")]
+extern crate im;
+#[cfg(feature = "preserve_order")]
+extern crate indexmap;
+extern crate memchr;
 extern crate ordered_float;
 
-use std::collections::{BTreeMap, BTreeSet};
+#[cfg(feature = "preserve_order")]
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::{self, Write};
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::mem;
 
+#[cfg(feature = "preserve_order")]
+use indexmap::{IndexMap, IndexSet};
 use ordered_float::OrderedFloat;
 
+pub mod borrowed;
+pub mod clojure;
+pub mod cst;
+pub mod diagnostic;
+pub mod event;
+#[cfg(feature = "intern")]
+pub mod intern;
+pub mod lexer;
 pub mod parser;
+pub mod ratio;
+pub mod reader;
+pub mod spanned;
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub use borrowed::BorrowedValue;
+pub use ratio::Ratio;
+pub use spanned::{Spanned, SpannedValue};
+
+// `im` has no insertion-ordered map or set, so maps and sets can't be both
+// insertion-ordered and persistent, and silently picking one would break code
+// written for the other. The aliases below are defined so that this is the
+// only error.
+#[cfg(all(feature = "preserve_order", feature = "persistent"))]
+compile_error!("the `preserve_order` and `persistent` features are mutually exclusive");
+
+/// The name of a `Value::Symbol` or `Value::Keyword`.
+///
+/// This is a `String` by default and an `intern::Interned` with the `intern`
+/// feature.
+#[cfg(not(feature = "intern"))]
+pub type Name = String;
+#[cfg(feature = "intern")]
+pub type Name = intern::Interned;
+
+/// The collection backing `Value::List` and `Value::Vector`.
+///
+/// This is a `Vec` by default and an `im::Vector`, which clones in O(1), with
+/// the `persistent` feature.
+#[cfg(not(feature = "persistent"))]
+pub type Vector = Vec<Value>;
+#[cfg(feature = "persistent")]
+pub type Vector = im::Vector<Value>;
+
+/// The collection backing `Value::Map`.
+///
+/// This is a `BTreeMap` by default, an insertion-ordered `IndexMap` with the
+/// `preserve_order` feature and an `im::OrdMap` with the `persistent` feature.
+/// Equality is order-insensitive either way. The two features can't be
+/// enabled together.
+#[cfg(not(any(feature = "preserve_order", feature = "persistent")))]
+pub type Map = BTreeMap<Value, Value>;
+#[cfg(feature = "preserve_order")]
+pub type Map = IndexMap<Value, Value>;
+#[cfg(all(feature = "persistent", not(feature = "preserve_order")))]
+pub type Map = im::OrdMap<Value, Value>;
+
+/// The collection backing `Value::Set`; see `Map`.
+#[cfg(not(any(feature = "preserve_order", feature = "persistent")))]
+pub type Set = BTreeSet<Value>;
+#[cfg(feature = "preserve_order")]
+pub type Set = IndexSet<Value>;
+#[cfg(all(feature = "persistent", not(feature = "preserve_order")))]
+pub type Set = im::OrdSet<Value>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(not(feature = "preserve_order"), derive(PartialOrd, Ord))]
 pub enum Value {
     Nil,
     Boolean(bool),
     String(String),
     Char(char),
-    Symbol(String),
-    Keyword(String),
+    Symbol(Name),
+    Keyword(Name),
     Integer(i64),
     Float(OrderedFloat<f64>),
-    List(Vec<Value>),
-    Vector(Vec<Value>),
-    Map(BTreeMap<Value, Value>),
-    Set(BTreeSet<Value>),
+    /// A ratio like `22/7`, read in Clojure number mode.
+    Ratio(Ratio),
+    List(Vector),
+    Vector(Vector),
+    Map(Map),
+    Set(Set),
     Tagged(String, Box<Value>),
 }
 
+// `IndexMap` and `IndexSet` don't implement `Ord`, so with `preserve_order` it
+// is implemented by hand, treating maps and sets as unordered to stay
+// consistent with their `PartialEq`.
+#[cfg(feature = "preserve_order")]
+impl Value {
+    fn rank(&self) -> u8 {
+        match *self {
+            Value::Nil => 0,
+            Value::Boolean(_) => 1,
+            Value::String(_) => 2,
+            Value::Char(_) => 3,
+            Value::Symbol(_) => 4,
+            Value::Keyword(_) => 5,
+            Value::Integer(_) => 6,
+            Value::Float(_) => 7,
+            Value::Ratio(_) => 8,
+            Value::List(_) => 9,
+            Value::Vector(_) => 10,
+            Value::Map(_) => 11,
+            Value::Set(_) => 12,
+            Value::Tagged(_, _) => 13,
+        }
+    }
+}
+
+#[cfg(feature = "preserve_order")]
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Value) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(feature = "preserve_order")]
+impl Ord for Value {
+    fn cmp(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::Char(a), Value::Char(b)) => a.cmp(b),
+            (Value::Symbol(a), Value::Symbol(b)) => a.cmp(b),
+            (Value::Keyword(a), Value::Keyword(b)) => a.cmp(b),
+            (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
+            (Value::Float(a), Value::Float(b)) => a.cmp(b),
+            (Value::Ratio(a), Value::Ratio(b)) => a.cmp(b),
+            (Value::List(a), Value::List(b)) => a.cmp(b),
+            (Value::Vector(a), Value::Vector(b)) => a.cmp(b),
+            (Value::Map(a), Value::Map(b)) => {
+                let mut a = a.iter().collect::<Vec<_>>();
+                let mut b = b.iter().collect::<Vec<_>>();
+                a.sort();
+                b.sort();
+                a.cmp(&b)
+            }
+            (Value::Set(a), Value::Set(b)) => {
+                let mut a = a.iter().collect::<Vec<_>>();
+                let mut b = b.iter().collect::<Vec<_>>();
+                a.sort();
+                b.sort();
+                a.cmp(&b)
+            }
+            (Value::Tagged(a, x), Value::Tagged(b, y)) => (a, x).cmp(&(b, y)),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+/// Hashes consistently with `Eq`: `0.0` and `-0.0` hash the same, as do all
+/// NaNs, and maps and sets hash the same regardless of their iteration order.
+///
+/// Hashing walks the value once without allocating.
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Value::Nil => {}
+            Value::Boolean(b) => b.hash(state),
+            Value::String(s) => s.hash(state),
+            Value::Symbol(s) | Value::Keyword(s) => s.hash(state),
+            Value::Char(ch) => ch.hash(state),
+            Value::Integer(i) => i.hash(state),
+            Value::Float(OrderedFloat(f)) => float_bits(*f).hash(state),
+            Value::Ratio(r) => r.hash(state),
+            Value::List(items) | Value::Vector(items) => items.hash(state),
+            Value::Map(map) => {
+                map.len().hash(state);
+                hash_entries(map.iter(), state);
+            }
+            Value::Set(set) => {
+                set.len().hash(state);
+                hash_entries(set.iter(), state);
+            }
+            Value::Tagged(tag, value) => {
+                tag.hash(state);
+                value.hash(state);
+            }
+        }
+    }
+}
+
+// The bits of `f` with all values `OrderedFloat` considers equal mapped to the
+// same pattern.
+fn float_bits(f: f64) -> u64 {
+    if f.is_nan() {
+        f64::NAN.to_bits()
+    } else if f == 0.0 {
+        0
+    } else {
+        f.to_bits()
+    }
+}
+
+// A sorted map or set iterates in a canonical order, so its entries can be
+// hashed one after another.
+#[cfg(not(feature = "preserve_order"))]
+fn hash_entries<T: Hash, I: Iterator<Item = T>, H: Hasher>(items: I, state: &mut H) {
+    for item in items {
+        item.hash(state);
+    }
+}
+
+// Insertion order isn't canonical, so combine the entries' hashes with a
+// commutative operation instead.
+#[cfg(feature = "preserve_order")]
+fn hash_entries<T: Hash, I: Iterator<Item = T>, H: Hasher>(items: I, state: &mut H) {
+    use std::collections::hash_map::DefaultHasher;
+
+    let mut sum = 0u64;
+    for item in items {
+        let mut hasher = DefaultHasher::new();
+        item.hash(&mut hasher);
+        sum = sum.wrapping_add(hasher.finish());
+    }
+    sum.hash(state);
+}
+
+/// Prints the value as EDN. Map and set entries are printed in iteration
+/// order, which is the source order with the `preserve_order` feature.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Nil => f.write_str("nil"),
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::String(s) => {
+                f.write_char('"')?;
+                for ch in s.chars() {
+                    match ch {
+                        '\t' => f.write_str("\\t")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '"' => f.write_str("\\\"")?,
+                        ch => f.write_char(ch)?,
+                    }
+                }
+                f.write_char('"')
+            }
+            Value::Char(ch) => match ch {
+                '\n' => f.write_str("\\newline"),
+                '\r' => f.write_str("\\return"),
+                ' ' => f.write_str("\\space"),
+                '\t' => f.write_str("\\tab"),
+                // Other whitespace can't follow a `\`, and it's all in the BMP.
+                ch if ch.is_whitespace() => write!(f, "\\u{:04x}", *ch as u32),
+                ch => write!(f, "\\{}", ch),
+            },
+            Value::Symbol(s) => f.write_str(s),
+            Value::Keyword(s) => write!(f, ":{}", s),
+            Value::Integer(i) => write!(f, "{}", i),
+            Value::Float(OrderedFloat(x)) => {
+                if x.is_nan() {
+                    f.write_str("##NaN")
+                } else if x.is_infinite() {
+                    f.write_str(if *x > 0.0 { "##Inf" } else { "##-Inf" })
+                } else {
+                    // Unlike `Display`, `Debug` always prints a `.` or an
+                    // exponent, so the output reads back as a float.
+                    write!(f, "{:?}", x)
+                }
+            }
+            Value::Ratio(r) => write!(f, "{}", r),
+            Value::List(items) => write_seq(f, "(", items, ")"),
+            Value::Vector(items) => write_seq(f, "[", items, "]"),
+            Value::Map(map) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{} {}", key, value)?;
+                }
+                f.write_char('}')
+            }
+            Value::Set(set) => write_seq(f, "#{", set, "}"),
+            Value::Tagged(tag, value) => write!(f, "#{} {}", tag, value),
+        }
+    }
+}
+
+fn write_seq<'a, I>(f: &mut fmt::Formatter, open: &str, items: I, close: &str) -> fmt::Result
+where
+    I: IntoIterator<Item = &'a Value>,
+{
+    f.write_str(open)?;
+    for (i, item) in items.into_iter().enumerate() {
+        if i > 0 {
+            f.write_char(' ')?;
+        }
+        write!(f, "{}", item)?;
+    }
+    f.write_str(close)
+}
+
 impl From<bool> for Value {
     fn from(s: bool) -> Self {
         Value::Boolean(s)
@@ -64,6 +352,12 @@ impl From<OrderedFloat<f64>> for Value {
     }
 }
 
+impl From<Ratio> for Value {
+    fn from(s: Ratio) -> Self {
+        Value::Ratio(s)
+    }
+}
+
 impl<A> From<Vec<A>> for Value
 where
     Value: From<A>,
@@ -79,7 +373,7 @@ where
     Value: From<V>,
 {
     fn from(s: BTreeMap<K, V>) -> Self {
-        let mut map = BTreeMap::new();
+        let mut map = Map::new();
         for (k, v) in s {
             map.insert(Value::from(k), Value::from(v));
         }
@@ -92,10 +386,35 @@ where
     Value: From<A>,
 {
     fn from(s: BTreeSet<A>) -> Self {
-        let mut set = BTreeSet::new();
+        let mut set = Set::new();
         s.into_iter().for_each(|a| {
             set.insert(Value::from(a));
         });
         Value::Set(set)
     }
 }
+
+impl<K, V, S> From<HashMap<K, V, S>> for Value
+where
+    Value: From<K>,
+    Value: From<V>,
+    S: BuildHasher,
+{
+    fn from(s: HashMap<K, V, S>) -> Self {
+        let mut map = Map::new();
+        for (k, v) in s {
+            map.insert(Value::from(k), Value::from(v));
+        }
+        Value::Map(map)
+    }
+}
+
+impl<A, S> From<HashSet<A, S>> for Value
+where
+    Value: From<A>,
+    S: BuildHasher,
+{
+    fn from(s: HashSet<A, S>) -> Self {
+        Value::Set(s.into_iter().map(Value::from).collect())
+    }
+}
@@ -1,325 +1,1520 @@
-use std::collections::BTreeMap;
-use std::str::CharIndices;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::error;
+use std::fmt;
+use std::hash::Hash;
+use std::num::IntErrorKind;
 
+use memchr::{memchr, memchr2, memchr_iter, memrchr};
 use ordered_float::OrderedFloat;
 
-use Value;
+#[cfg(feature = "intern")]
+use intern::Interner;
+use lexer::{self, Token, TokenKind};
+use spanned::{Spanned, SpannedValue};
+use {BorrowedValue, Map, Name, Ratio, Value};
 
 pub struct Parser<'a> {
     str: &'a str,
-    chars: CharIndices<'a>,
+    pos: usize,
+    source_name: Option<String>,
+    options: ParserOptions,
+    // The positions of the offsets errors have been made at so far.
+    positions: RefCell<BTreeMap<usize, Position>>,
+    #[cfg(feature = "intern")]
+    interner: Interner,
 }
 
+/// What a `Parser` accepts.
+///
+/// `ParserOptions::new()`, the default, reads EDN checking symbols, keywords
+/// and numbers, but not tags, against the spec.
+/// `strict()` checks them too, and `lenient()` also reads what Clojure prints
+/// that isn't EDN. Each
+/// option can then be changed on its own:
+///
+/// ```text
+/// let options = ParserOptions::strict().tag("myapp/Person").allow_comments(false);
+/// let mut parser = Parser::new(input).with_options(options);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParserOptions {
+    duplicates: bool,
+    comments: bool,
+    commas: bool,
+    discard: bool,
+    tagged: bool,
+    invalid_symbols: bool,
+    unknown_tags: bool,
+    tags: Vec<String>,
+    leading_zeros: bool,
+    clojure_numbers: bool,
+    symbolic_floats: bool,
+    overflow: Overflow,
+    max_depth: usize,
+    max_values: usize,
+    max_string_length: usize,
+    max_collection_length: usize,
+    pub(crate) max_input_size: usize,
+}
+
+/// How an integer that doesn't fit in an `i64` is read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overflow {
+    /// As an `ErrorKind::NumberOverflow` error.
+    Error,
+    /// As the nearest `Value::Float`.
+    Float,
+}
+
+/// An error in the bytes `lo..hi` of the input.
+///
+/// It's displayed as `name:line:column: message`, leaving out the name if the
+/// input doesn't have one. The message is the `Display` of `kind`.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Error {
     pub lo: usize,
     pub hi: usize,
-    pub message: String,
+    pub kind: ErrorKind,
+    /// The line and column of `lo` and `hi`.
+    pub start: Position,
+    pub end: Position,
+    /// The name of the input, e.g. its file path, if it was given one.
+    pub source_name: Option<String>,
+}
+
+/// What went wrong. Each kind's `Display` is its message, e.g. ``unclosed
+/// `(` ``, which won't change.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A list, vector, map or set that isn't closed, opened by `open`.
+    UnclosedDelimiter { open: &'static str },
+    /// A closing delimiter that doesn't close anything.
+    UnexpectedDelimiter { delimiter: char },
+    /// A character that can't start a value.
+    UnexpectedChar { ch: char },
+    /// A string that isn't closed.
+    UnclosedString,
+    /// An escape like `\x` in a string.
+    InvalidEscape { escape: String },
+    /// A character literal like `\foo`.
+    InvalidCharLiteral { literal: String },
+    /// A `#` that isn't followed by `{`, `_` or a tag.
+    InvalidDispatch { dispatch: String },
+    /// A map with a key that has no value.
+    OddMapEntries,
+    /// Syntax that `ParserOptions` doesn't allow, e.g. `"comments"`.
+    NotAllowed { syntax: &'static str },
+    /// A symbol or keyword the spec doesn't allow, e.g. `a/b/c`, `::a` or
+    /// `:/`, unless read with `ParserOptions::allow_invalid_symbols`, or a `:`
+    /// without a name, which is never allowed.
+    InvalidSymbol { symbol: String },
+    /// A number followed by something other than a delimiter, like `1a`, a
+    /// number without digits before its `.`, like `.5`, a Clojure integer
+    /// like `0xFF` or `2r12` that isn't allowed or has invalid digits, or a
+    /// ratio that isn't allowed or is divided by zero.
+    InvalidNumber { number: String },
+    /// A tag that isn't `inst`, `uuid` or one given to `ParserOptions::tag`,
+    /// read with `ParserOptions::allow_unknown_tags(false)`.
+    UnknownTag { tag: String },
+    /// A number like `007`, unless read with
+    /// `ParserOptions::allow_leading_zeros`.
+    LeadingZeros { number: String },
+    /// A map key that's already in the map, where it's at
+    /// `first_lo..first_hi`.
+    DuplicateKey { first_lo: usize, first_hi: usize },
+    /// A set element that's already in the set, where it's at
+    /// `first_lo..first_hi`.
+    DuplicateElement { first_lo: usize, first_hi: usize },
+    /// A tag that isn't followed by a value.
+    MissingTaggedValue,
+    /// A `#_` that isn't followed by a value.
+    MissingDiscardedValue,
+    /// An integer, or a ratio's numerator or denominator, that doesn't fit in
+    /// an `i64`.
+    NumberOverflow { number: String },
+    /// Input that goes over one of the limits set with `ParserOptions`, e.g.
+    /// `max_depth`, whose value is `max`.
+    LimitExceeded { limit: Limit, max: usize },
+    /// Input to a `PushParser` or `ReaderParser` that isn't UTF-8.
+    InvalidUtf8,
+    /// An error reading from the `io::BufRead` of a `ReaderParser`.
+    Io { message: String },
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ErrorKind::UnclosedDelimiter { open } => write!(f, "unclosed `{}`", open),
+            ErrorKind::UnexpectedDelimiter { delimiter } => write!(f, "unexpected `{}`", delimiter),
+            ErrorKind::UnexpectedChar { ch } => write!(f, "unexpected `{}`", ch),
+            ErrorKind::UnclosedString => f.write_str("expected closing `\"`, found EOF"),
+            ErrorKind::InvalidEscape { ref escape } => {
+                write!(f, "invalid string escape `{}`", escape)
+            }
+            ErrorKind::InvalidCharLiteral { ref literal } => {
+                write!(f, "invalid char literal `{}`", literal)
+            }
+            ErrorKind::InvalidDispatch { ref dispatch } => {
+                write!(f, "invalid dispatch `{}`", dispatch)
+            }
+            ErrorKind::OddMapEntries => f.write_str("odd number of items in a Map"),
+            ErrorKind::NotAllowed { syntax } => write!(f, "{} aren't allowed", syntax),
+            ErrorKind::InvalidSymbol { ref symbol } => write!(f, "invalid symbol `{}`", symbol),
+            ErrorKind::InvalidNumber { ref number } => write!(f, "invalid number `{}`", number),
+            ErrorKind::UnknownTag { ref tag } => write!(f, "unknown tag `#{}`", tag),
+            ErrorKind::LeadingZeros { ref number } => {
+                write!(f, "number `{}` has leading zeros", number)
+            }
+            ErrorKind::DuplicateKey { .. } => f.write_str("duplicate map key"),
+            ErrorKind::DuplicateElement { .. } => f.write_str("duplicate set element"),
+            ErrorKind::MissingTaggedValue => f.write_str("malformed tagged value"),
+            ErrorKind::MissingDiscardedValue => f.write_str("malformed discarded value"),
+            ErrorKind::NumberOverflow { ref number } => {
+                write!(f, "number `{}` is out of range", number)
+            }
+            ErrorKind::LimitExceeded { limit, max } => {
+                write!(f, "{} exceeds the limit of {}", limit, max)
+            }
+            ErrorKind::InvalidUtf8 => f.write_str("invalid UTF-8"),
+            ErrorKind::Io { ref message } => f.write_str(message),
+        }
+    }
+}
+
+/// Which of the limits set with `ParserOptions` input went over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Limit {
+    /// `ParserOptions::max_depth`.
+    Depth,
+    /// `ParserOptions::max_values`.
+    Values,
+    /// `ParserOptions::max_string_length`.
+    StringLength,
+    /// `ParserOptions::max_collection_length`.
+    CollectionLength,
+    /// `ParserOptions::max_input_size`.
+    InputSize,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Limit::Depth => "nesting depth",
+            Limit::Values => "number of values",
+            Limit::StringLength => "length",
+            Limit::CollectionLength => "collection length",
+            Limit::InputSize => "input size",
+        })
+    }
+}
+
+/// A position in the input, as a 1-based line and column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Position {
+    pub line: usize,
+    /// The column counted in characters (Unicode scalar values).
+    pub column: usize,
+    /// The column counted in UTF-16 code units, like e.g. the Language Server
+    /// Protocol and JavaScript count them.
+    pub utf16_column: usize,
+}
+
+impl Error {
+    // Creates the error for `lo..hi` of `bytes`.
+    pub(crate) fn new(bytes: &[u8], lo: usize, hi: usize, kind: ErrorKind) -> Error {
+        let mut start = Position::start();
+        start.advance(&bytes[..lo]);
+        let mut end = start;
+        end.advance(&bytes[lo..hi]);
+        Error {
+            lo,
+            hi,
+            kind,
+            start,
+            end,
+            source_name: None,
+        }
+    }
+
+    /// Names the input this error is in, e.g. with its file path.
+    pub fn with_source_name(mut self, name: &str) -> Error {
+        self.source_name = Some(name.into());
+        self
+    }
+
+    // Moves an error in a part of the input that starts at `offset` and
+    // `position` to where it is in the whole input.
+    pub(crate) fn after(self, offset: usize, position: Position) -> Error {
+        let kind = match self.kind {
+            ErrorKind::DuplicateKey { first_lo, first_hi } => ErrorKind::DuplicateKey {
+                first_lo: first_lo + offset,
+                first_hi: first_hi + offset,
+            },
+            ErrorKind::DuplicateElement { first_lo, first_hi } => ErrorKind::DuplicateElement {
+                first_lo: first_lo + offset,
+                first_hi: first_hi + offset,
+            },
+            kind => kind,
+        };
+        Error {
+            lo: self.lo + offset,
+            hi: self.hi + offset,
+            kind,
+            start: self.start.after(position),
+            end: self.end.after(position),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref name) = self.source_name {
+            write!(f, "{}:", name)?;
+        }
+        write!(
+            f,
+            "{}:{}: {}",
+            self.start.line, self.start.column, self.kind
+        )
+    }
+}
+
+impl error::Error for Error {}
+
+impl Position {
+    pub(crate) fn start() -> Position {
+        Position {
+            line: 1,
+            column: 1,
+            utf16_column: 1,
+        }
+    }
+
+    // Moves past `bytes`. Lines end at `\n`, and invalid UTF-8 is counted as
+    // if it were valid.
+    pub(crate) fn advance(&mut self, bytes: &[u8]) {
+        let rest = match memrchr(b'\n', bytes) {
+            Some(at) => {
+                self.line += memchr_iter(b'\n', bytes).count();
+                self.column = 1;
+                self.utf16_column = 1;
+                &bytes[at + 1..]
+            }
+            None => bytes,
+        };
+        for &byte in rest {
+            // Only the first byte of each character counts, and characters of
+            // four bytes take two UTF-16 code units.
+            if byte & 0xc0 != 0x80 {
+                self.column += 1;
+                self.utf16_column += if let 0xf0..=0xf4 = byte { 2 } else { 1 };
+            }
+        }
+    }
+
+    // Makes a position in a part of the input that starts at `base` relative
+    // to the whole input.
+    pub(crate) fn after(self, base: Position) -> Position {
+        if self.line == 1 {
+            Position {
+                line: base.line,
+                column: base.column + self.column - 1,
+                utf16_column: base.utf16_column + self.utf16_column - 1,
+            }
+        } else {
+            Position {
+                line: base.line + self.line - 1,
+                ..self
+            }
+        }
+    }
+}
+
+// A collection, tag or `#_` that `Parser::value` is reading the inside of.
+enum Frame<'a, T> {
+    Collection {
+        open: Token,
+        close: TokenKind,
+        items: Vec<T>,
+        // The spans of the items of maps and sets, for duplicate errors.
+        spans: Vec<(usize, usize)>,
+    },
+    Tag {
+        token: Token,
+        tag: &'a str,
+    },
+    Discard {
+        lo: usize,
+    },
 }
 
 impl<'a> Parser<'a> {
     pub fn new(str: &'a str) -> Parser<'a> {
         Parser {
-            str: str,
-            chars: str.char_indices(),
+            str,
+            pos: 0,
+            source_name: None,
+            options: ParserOptions::new(),
+            positions: RefCell::new(BTreeMap::new()),
+            #[cfg(feature = "intern")]
+            interner: Interner::new(),
+        }
+    }
+
+    /// Names the input, e.g. with its file path, for the errors it returns.
+    pub fn with_source_name(mut self, name: &str) -> Parser<'a> {
+        self.source_name = Some(name.into());
+        self
+    }
+
+    /// Reads the input with `options` instead of `ParserOptions::new()`.
+    pub fn with_options(mut self, options: ParserOptions) -> Parser<'a> {
+        self.options = options;
+        self
+    }
+
+    /// Creates a parser that interns names into `interner`.
+    #[cfg(feature = "intern")]
+    pub fn with_interner(str: &'a str, interner: Interner) -> Parser<'a> {
+        Parser {
+            str,
+            pos: 0,
+            source_name: None,
+            options: ParserOptions::new(),
+            positions: RefCell::new(BTreeMap::new()),
+            interner,
         }
     }
 
+    /// Returns the interner holding every name read so far.
+    #[cfg(feature = "intern")]
+    pub fn into_interner(self) -> Interner {
+        self.interner
+    }
+
+    // The offset of the first byte that hasn't been read.
+    pub(crate) fn position(&self) -> usize {
+        self.pos
+    }
+
+    // Continues reading from `pos` if it's further along, e.g. from the end of
+    // an error that didn't consume any input.
+    pub(crate) fn skip_to(&mut self, pos: usize) {
+        self.pos = self.pos.max(pos);
+    }
+
     pub fn read(&mut self) -> Option<Result<Value, Error>> {
-        self.whitespace();
-
-        self.chars.clone().next().map(|(pos, ch)| match (pos, ch) {
-            (start, '0'...'9') => {
-                let end = self.advance_while(|ch| ch.is_digit(10));
-                if self.peek() == Some('.') {
-                    self.chars.next();
-                    let end = self.advance_while(|ch| ch.is_digit(10));
-                    Ok(Value::Float(OrderedFloat(
-                        self.str[start..end].parse().unwrap(),
-                    )))
-                } else {
-                    Ok(Value::Integer(self.str[start..end].parse().unwrap()))
-                }
+        self.read_with()
+    }
+
+    /// Like `read`, but borrows strings, symbols, keywords and tags from the
+    /// input instead of copying them. Strings containing escapes are the only
+    /// ones that have to be allocated.
+    pub fn read_borrowed(&mut self) -> Option<Result<BorrowedValue<'a>, Error>> {
+        self.read_with()
+    }
+
+    /// Like `read`, but every value, including map keys and values, set
+    /// members and tags, carries the span of the input it was read from.
+    pub fn read_spanned(&mut self) -> Option<Result<Spanned<SpannedValue>, Error>> {
+        self.read_with()
+    }
+
+    fn read_with<T: Build<'a>>(&mut self) -> Option<Result<T, Error>> {
+        let max = self.options.max_input_size;
+        if self.pos == 0 && self.str.len() > max {
+            let mut lo = max;
+            while !self.str.is_char_boundary(lo) {
+                lo -= 1;
             }
-            (start, ch @ '+') | (start, ch @ '-') => {
-                self.chars.next();
-                match self.peek() {
-                    Some('0'...'9') => {
-                        let start = if ch == '+' { start + 1 } else { start };
-                        let end = self.advance_while(|ch| ch.is_digit(10));
-                        if self.peek() == Some('.') {
-                            self.chars.next();
-                            let end = self.advance_while(|ch| ch.is_digit(10));
-                            Ok(Value::Float(OrderedFloat(
-                                self.str[start..end].parse().unwrap(),
-                            )))
-                        } else {
-                            Ok(Value::Integer(self.str[start..end].parse().unwrap()))
+            let err = self.limit(lo, self.str.len(), Limit::InputSize, max);
+            self.pos = self.str.len();
+            return Some(Err(err));
+        }
+        match self.value() {
+            Ok(Some(value)) => Some(Ok(value)),
+            Ok(None) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+
+    // Returns the next token that isn't whitespace, a comma or a comment,
+    // unless commas or comments aren't allowed.
+    pub(crate) fn significant(&mut self) -> Option<Token> {
+        if self.options.commas && self.options.comments {
+            self.pos = lexer::skip_trivia(self.str, self.pos);
+        }
+        while self.pos < self.str.len() {
+            let token = lexer::lex(self.str, self.pos);
+            self.pos = token.hi;
+            match token.kind {
+                TokenKind::Whitespace => {}
+                TokenKind::Comma if self.options.commas => {}
+                TokenKind::Comment if self.options.comments => {}
+                _ => return Some(token),
+            }
+        }
+        None
+    }
+
+    // Reads the next value, skipping any discarded with `#_`. The collections,
+    // tags and `#_`s it's inside of are kept on `stack` rather than the call
+    // stack, so that deeply nested input can't overflow it.
+    fn value<T: Build<'a>>(&mut self) -> Result<Option<T>, Error> {
+        let mut stack: Vec<Frame<'a, T>> = vec![];
+        let mut depth = 0;
+        let mut values = 0;
+        loop {
+            let token = match self.significant() {
+                Some(token) => token,
+                None => {
+                    return match stack.pop() {
+                        None => Ok(None),
+                        Some(Frame::Collection { open, .. }) => {
+                            Err(self.unclosed(open.lo, open.hi))
                         }
-                    }
-                    Some(ch) if is_symbol_tail(ch) => {
-                        let end = self.advance_while(is_symbol_tail);
-                        Ok(Value::Symbol(self.str[start..end].into()))
-                    }
-                    None | Some(' ') | Some('\t') | Some('\n') => Ok(Value::Symbol(ch.to_string())),
-                    _ => unimplemented!(),
+                        Some(Frame::Tag { token, .. }) => {
+                            Err(self.missing(token.lo + 1, ErrorKind::MissingTaggedValue))
+                        }
+                        Some(Frame::Discard { lo }) => {
+                            Err(self.missing(lo, ErrorKind::MissingDiscardedValue))
+                        }
+                    };
+                }
+            };
+            let closes = match stack.last() {
+                Some(&Frame::Collection { close, .. }) => close == token.kind,
+                _ => false,
+            };
+            if token.kind == TokenKind::Discard {
+                if !self.options.discard {
+                    let syntax = "discarded values";
+                    return Err(self.error_at(
+                        token.lo,
+                        token.hi,
+                        ErrorKind::NotAllowed { syntax },
+                    ));
                 }
+                stack.push(Frame::Discard { lo: token.lo });
+                continue;
             }
-            (start, '.') => {
-                self.chars.next();
-                if let Some('0'...'9') = self.peek() {
-                    let end = self.advance_while(|ch| ch.is_digit(10));
-                    Ok(Value::Float(OrderedFloat(
-                        self.str[start..end].parse().unwrap(),
-                    )))
-                } else {
-                    let end = self.advance_while(is_symbol_tail);
-                    Ok(Value::Symbol(self.str[start..end].into()))
+            if !closes {
+                self.check_limits(token, &stack, values)?;
+                if token.kind != TokenKind::Tag {
+                    values += 1;
                 }
             }
-            (start, '\\') => {
-                self.chars.next();
-                let start = start + 1;
-                let end = self.advance_while(|ch| !ch.is_whitespace());
-                Ok(Value::Char(match &self.str[start..end] {
-                    "newline" => '\n',
-                    "return" => '\r',
-                    "space" => ' ',
-                    "tab" => '\t',
-                    otherwise => {
-                        if otherwise.chars().count() == 1 {
-                            otherwise.chars().next().unwrap()
-                        } else {
-                            return Err(Error {
-                                lo: start - 1,
-                                hi: end,
-                                message: format!("invalid char literal `\\{}`", otherwise),
-                            });
-                        }
+
+            let (mut value, mut lo) = match token.kind {
+                _ if closes => match stack.pop() {
+                    Some(Frame::Collection {
+                        open, items, spans, ..
+                    }) => {
+                        depth -= 1;
+                        (self.collection(open, items, &spans)?, open.lo)
                     }
-                }))
-            }
-            (start, '"') => {
-                self.chars.next();
-                let mut string = String::new();
-                loop {
-                    match self.chars.next() {
-                        Some((_, '"')) => return Ok(Value::String(string)),
-                        Some((_, '\\')) => {
-                            string.push(match self.chars.next() {
-                                Some((_, 't')) => '\t',
-                                Some((_, 'r')) => '\r',
-                                Some((_, 'n')) => '\n',
-                                Some((_, '\\')) => '\\',
-                                Some((_, '"')) => '\"',
-                                Some((pos, ch)) => {
-                                    return Err(Error {
-                                        lo: pos - 1,
-                                        hi: pos + 1,
-                                        message: format!("invalid string escape `\\{}`", ch),
-                                    })
-                                }
-                                None => unimplemented!(),
-                            });
-                        }
-                        Some((_, ch)) => string.push(ch),
-                        None => {
-                            return Err(Error {
-                                lo: start,
-                                hi: self.str.len(),
-                                message: "expected closing `\"`, found EOF".into(),
-                            })
+                    _ => unreachable!(),
+                },
+                TokenKind::LeftParen
+                | TokenKind::LeftBracket
+                | TokenKind::LeftBrace
+                | TokenKind::HashBrace => {
+                    depth = self.nest(depth, token)?;
+                    let close = match token.kind {
+                        TokenKind::LeftParen => TokenKind::RightParen,
+                        TokenKind::LeftBracket => TokenKind::RightBracket,
+                        _ => TokenKind::RightBrace,
+                    };
+                    stack.push(Frame::Collection {
+                        open: token,
+                        close,
+                        items: vec![],
+                        spans: vec![],
+                    });
+                    continue;
+                }
+                TokenKind::Tag => {
+                    let tag = &self.text(token)[1..];
+                    self.check_tag(token, tag)?;
+                    depth = self.nest(depth, token)?;
+                    stack.push(Frame::Tag { token, tag });
+                    continue;
+                }
+                _ => (self.scalar::<T>(token)?.span(token.lo, self.pos), token.lo),
+            };
+
+            // The value completes any tags before it, and is then an item of a
+            // collection, discarded or the value read.
+            loop {
+                match stack.last_mut() {
+                    None => return Ok(Some(value)),
+                    Some(&mut Frame::Collection {
+                        open,
+                        ref mut items,
+                        ref mut spans,
+                        ..
+                    }) => {
+                        if open.kind == TokenKind::LeftBrace || open.kind == TokenKind::HashBrace {
+                            spans.push((lo, self.pos));
                         }
+                        items.push(value);
+                        break;
+                    }
+                    _ => {}
+                }
+                match stack.pop() {
+                    Some(Frame::Tag { token, tag }) => {
+                        depth -= 1;
+                        value = T::tagged(tag, value).span(token.lo, self.pos);
+                        lo = token.lo;
                     }
+                    _ => break,
                 }
             }
-            (start, ':') => {
-                self.chars.next();
-                let end = self.advance_while(is_symbol_tail);
-                Ok(Value::Keyword(self.str[start + 1..end].into()))
+        }
+    }
+
+    // Builds the collection opened by `open`, which has just been closed. The
+    // items of a map or set span `spans`.
+    fn collection<T: Build<'a>>(
+        &self,
+        open: Token,
+        items: Vec<T>,
+        spans: &[(usize, usize)],
+    ) -> Result<T, Error> {
+        let value = match open.kind {
+            TokenKind::LeftParen => T::list(items),
+            TokenKind::LeftBracket => T::vector(items),
+            TokenKind::LeftBrace => {
+                if items.len() % 2 == 1 {
+                    return Err(self.odd_map(open.lo));
+                }
+                self.unique(open, &items, spans)?;
+                let mut entries = Vec::with_capacity(items.len() / 2);
+                let mut iter = items.into_iter();
+                while let (Some(key), Some(value)) = (iter.next(), iter.next()) {
+                    entries.push((key, value));
+                }
+                T::map(entries)
             }
-            (start, open @ '(') | (start, open @ '[') | (start, open @ '{') => {
-                let close = match open {
-                    '(' => ')',
-                    '[' => ']',
-                    '{' => '}',
-                    _ => unreachable!(),
-                };
+            _ => {
+                self.unique(open, &items, spans)?;
+                T::set(items)
+            }
+        };
+        Ok(value.span(open.lo, self.pos))
+    }
 
-                self.chars.next();
-                let mut items = vec![];
-                loop {
-                    self.whitespace();
-
-                    if self.peek() == Some(close) {
-                        self.chars.next();
-                        return Ok(match open {
-                            '(' => Value::List(items),
-                            '[' => Value::Vector(items),
-                            '{' => {
-                                let mut map = BTreeMap::new();
-                                let mut iter = items.into_iter();
-                                while let Some(key) = iter.next() {
-                                    if let Some(value) = iter.next() {
-                                        map.insert(key, value);
-                                    } else {
-                                        let end = self.chars
-                                            .clone()
-                                            .next()
-                                            .map(|(pos, _)| pos)
-                                            .unwrap_or(self.str.len());
-                                        return Err(Error {
-                                            lo: start,
-                                            hi: end,
-                                            message: "odd number of items in a Map".into(),
-                                        });
-                                    }
-                                }
-                                Value::Map(map)
-                            }
-                            _ => unreachable!(),
-                        });
-                    }
+    // Checks that the value starting with `token`, after `values` others in
+    // the same read, doesn't go over a limit, unless it's a collection or tag
+    // whose nesting `nest` checks.
+    fn check_limits<T>(
+        &self,
+        token: Token,
+        stack: &[Frame<'a, T>],
+        values: usize,
+    ) -> Result<(), Error> {
+        let options = &self.options;
+        if values >= options.max_values {
+            return Err(self.limit(token.lo, token.hi, Limit::Values, options.max_values));
+        }
+        if let Some(&Frame::Collection {
+            open, ref items, ..
+        }) = stack.last()
+        {
+            let len = match open.kind {
+                TokenKind::LeftBrace => items.len() / 2,
+                _ => items.len(),
+            };
+            let max = options.max_collection_length;
+            if len >= max {
+                return Err(self.limit(token.lo, token.hi, Limit::CollectionLength, max));
+            }
+        }
+        let len = match token.kind {
+            TokenKind::String => token.hi - token.lo - 2,
+            TokenKind::Symbol => token.hi - token.lo,
+            TokenKind::Keyword | TokenKind::Tag => token.hi - token.lo - 1,
+            _ => 0,
+        };
+        let max = options.max_string_length;
+        if len > max {
+            return Err(self.limit(token.lo, token.hi, Limit::StringLength, max));
+        }
+        Ok(())
+    }
 
-                    match self.read() {
-                        Some(Ok(value)) => items.push(value),
-                        Some(Err(err)) => return Err(err),
-                        None => {
-                            return Err(Error {
-                                lo: start,
-                                hi: self.str.len(),
-                                message: format!("unclosed `{}`", open),
-                            })
-                        }
-                    }
+    // Returns the depth inside the collection or tag starting with `token`,
+    // which is nested `depth` deep.
+    pub(crate) fn nest(&self, depth: usize, token: Token) -> Result<usize, Error> {
+        let max = self.options.max_depth;
+        if depth >= max {
+            return Err(self.limit(token.lo, token.hi, Limit::Depth, max));
+        }
+        Ok(depth + 1)
+    }
+
+    // Reads a value that isn't a collection or tagged.
+    fn scalar<T: Build<'a>>(&mut self, token: Token) -> Result<T, Error> {
+        let text = self.text(token);
+        Ok(match token.kind {
+            TokenKind::Integer => self.integer(token)?,
+            TokenKind::Ratio => self.ratio(token)?,
+            // `Value` has no arbitrary precision numbers, so the `M` suffix
+            // asking for one is ignored, as is an integer's `N`.
+            TokenKind::Float => {
+                self.check_digits(token)?;
+                T::float(text.trim_end_matches('M').parse().unwrap())
+            }
+            TokenKind::String => {
+                // The lexer has already found the closing quote.
+                let contents = &text[1..text.len() - 1];
+                match memchr(b'\\', contents.as_bytes()) {
+                    Some(_) => T::string(self.string(token.lo)?),
+                    None => T::string(Cow::Borrowed(contents)),
                 }
             }
-            (start, '#') => {
-                self.chars.next();
-                match self.chars.next() {
-                    Some((_, open @ '{')) => {
-                        let close = '}';
-                        let mut items = vec![];
-                        loop {
-                            self.whitespace();
-
-                            if self.peek() == Some(close) {
-                                self.chars.next();
-                                return Ok(Value::Set(items.into_iter().collect()));
-                            }
-
-                            match self.read() {
-                                Some(Ok(value)) => items.push(value),
-                                Some(Err(err)) => return Err(err),
-                                None => {
-                                    return Err(Error {
-                                        lo: start,
-                                        hi: self.str.len(),
-                                        message: format!("unclosed `#{}`", open),
-                                    })
-                                }
-                            }
-                        }
-                    }
-                    Some((start, ch)) if is_symbol_head(ch) => {
-                        self.chars.next();
-                        let end = self.advance_while(is_symbol_tail);
-
-                        let tag = &self.str[start..end];
-                        let value = self.read();
-
-                        match value {
-                            Some(Ok(v)) => return Ok(Value::Tagged(tag.into(), Box::new(v))),
-                            Some(e) => return e,
-                            None => {
-                                return Err(Error {
-                                    lo: start,
-                                    hi: self.str.len(),
-                                    message: "malformed tagged value".into(),
-                                })
-                            }
+            TokenKind::Char => T::char(lexer::char_name(&text[1..]).unwrap()),
+            TokenKind::Keyword => {
+                self.check_symbol(token)?;
+                T::keyword(self, &text[1..])
+            }
+            TokenKind::Symbol => match text {
+                "true" => T::boolean(true),
+                "false" => T::boolean(false),
+                "nil" => T::nil(),
+                otherwise => {
+                    self.check_symbol(token)?;
+                    T::symbol(self, otherwise)
+                }
+            },
+            // `##Inf`, `##-Inf` and `##NaN` are lexed as an invalid dispatch
+            // followed by a symbol.
+            TokenKind::Error
+                if self.options.symbolic_floats && text == "##" && token.hi < self.str.len() =>
+            {
+                let name = lexer::lex(self.str, token.hi);
+                let f = match (name.kind, self.text(name)) {
+                    (TokenKind::Symbol, "Inf") => f64::INFINITY,
+                    (TokenKind::Symbol, "-Inf") => f64::NEG_INFINITY,
+                    (TokenKind::Symbol, "NaN") => f64::NAN,
+                    _ => return Err(self.error(token)),
+                };
+                self.pos = name.hi;
+                T::float(f)
+            }
+            _ => return Err(self.error(token)),
+        })
+    }
+
+    // Reads an integer, which in Clojure number mode can be written in another
+    // radix.
+    fn integer<T: Build<'a>>(&self, token: Token) -> Result<T, Error> {
+        let text = self.text(token);
+        let unsigned = text.trim_start_matches(['+', '-']);
+        let plain = unsigned.trim_end_matches('N');
+        let (radix, digits) = match unsigned.as_bytes() {
+            [b'0', b'x' | b'X', ..] => (16, &plain[2..]),
+            _ => match unsigned.find(['r', 'R']) {
+                Some(at) => (unsigned[..at].parse().unwrap(), &unsigned[at + 1..]),
+                None if self.options.clojure_numbers
+                    && plain.len() > 1
+                    && plain.starts_with('0') =>
+                {
+                    (8, &plain[1..])
+                }
+                None => (10, plain),
+            },
+        };
+        let invalid = || {
+            let number = text.into();
+            self.error_at(token.lo, token.hi, ErrorKind::InvalidNumber { number })
+        };
+        if radix != 10 && !self.options.clojure_numbers || !(2..=36).contains(&radix) {
+            return Err(invalid());
+        }
+        let negative = text.starts_with('-');
+        let parsed = match radix {
+            10 => {
+                self.check_digits(token)?;
+                text.trim_end_matches('N').parse()
+            }
+            _ if negative => i64::from_str_radix(&format!("-{}", digits), radix),
+            _ => i64::from_str_radix(digits, radix),
+        };
+        match parsed {
+            Ok(i) => Ok(T::integer(i)),
+            Err(ref err) if *err.kind() == IntErrorKind::InvalidDigit => Err(invalid()),
+            Err(_) if self.options.overflow == Overflow::Float => {
+                let f = match radix {
+                    10 => text.trim_end_matches('N').parse().unwrap(),
+                    _ => {
+                        let f = digits.chars().fold(0.0, |f, ch| {
+                            f * radix as f64 + ch.to_digit(radix).unwrap() as f64
+                        });
+                        if negative {
+                            -f
+                        } else {
+                            f
                         }
                     }
-                    _ => unimplemented!(),
+                };
+                Ok(T::float(f))
+            }
+            Err(_) => {
+                let number = text.into();
+                Err(self.error_at(token.lo, token.hi, ErrorKind::NumberOverflow { number }))
+            }
+        }
+    }
+
+    // Reads a ratio in Clojure number mode, as an integer if it's a whole
+    // number.
+    fn ratio<T: Build<'a>>(&self, token: Token) -> Result<T, Error> {
+        let text = self.text(token);
+        let at = text.find('/').unwrap();
+        let (numer, denom) = (&text[..at], &text[at + 1..]);
+        let overflow = || {
+            if self.options.overflow == Overflow::Float {
+                let numer: f64 = numer.parse().unwrap();
+                return Ok(T::float(numer / denom.parse::<f64>().unwrap()));
+            }
+            let number = text.into();
+            Err(self.error_at(token.lo, token.hi, ErrorKind::NumberOverflow { number }))
+        };
+        match (numer.parse(), denom.parse()) {
+            _ if !self.options.clojure_numbers => {}
+            (_, Ok(0)) => {}
+            (Ok(numer), Ok(denom)) => {
+                return match Ratio::new(numer, denom) {
+                    Some(ratio) if ratio.denom() == 1 => Ok(T::integer(ratio.numer())),
+                    Some(ratio) => Ok(T::ratio(ratio)),
+                    None => overflow(),
                 }
             }
-            (start, ch) if is_symbol_head(ch) => {
-                self.chars.next();
-                let end = self.advance_while(is_symbol_tail);
-                Ok(match &self.str[start..end] {
-                    "true" => Value::Boolean(true),
-                    "false" => Value::Boolean(false),
-                    "nil" => Value::Nil,
-                    otherwise => Value::Symbol(otherwise.into()),
-                })
-            }
-            (_, '/') => {
-                self.chars.next();
-                Ok(Value::Symbol("/".into()))
-            }
-            _ => unimplemented!(),
-        })
+            _ => return overflow(),
+        }
+        let number = text.into();
+        Err(self.error_at(token.lo, token.hi, ErrorKind::InvalidNumber { number }))
     }
 
-    fn peek(&self) -> Option<char> {
-        self.chars.clone().next().map(|(_, ch)| ch)
+    // Checks a symbol or keyword against the spec, if asked to.
+    fn check_symbol(&self, token: Token) -> Result<(), Error> {
+        if self.options.invalid_symbols {
+            return Ok(());
+        }
+        let text = self.text(token);
+        let valid = match token.kind {
+            // `/` is a symbol but not a keyword's name.
+            TokenKind::Keyword => {
+                let name = &text[1..];
+                !name.starts_with(':') && name != "/" && lexer::is_valid_symbol(name)
+            }
+            _ => lexer::is_valid_symbol(text),
+        };
+        if valid {
+            return Ok(());
+        }
+        let symbol = text.into();
+        Err(self.error_at(token.lo, token.hi, ErrorKind::InvalidSymbol { symbol }))
     }
 
-    fn whitespace(&mut self) {
-        loop {
-            // Skip whitespace.
-            self.advance_while(|ch| ch.is_whitespace() || ch == ',');
-            // Skip comment if present.
-            if self.chars.clone().next().map_or(false, |(_, ch)| ch == ';') {
-                self.advance_while(|ch| ch != '\n');
-                self.chars.next();
-            } else {
-                // Otherwise we're done.
-                return;
+    // Checks that the tag `tag` is allowed and known, if asked to.
+    fn check_tag(&self, token: Token, tag: &str) -> Result<(), Error> {
+        let kind = if !self.options.tagged {
+            ErrorKind::NotAllowed {
+                syntax: "tagged values",
             }
+        } else if self.options.unknown_tags
+            || tag == "inst"
+            || tag == "uuid"
+            || self.options.tags.iter().any(|known| known == tag)
+        {
+            return Ok(());
+        } else {
+            ErrorKind::UnknownTag { tag: tag.into() }
+        };
+        Err(self.error_at(token.lo, token.hi, kind))
+    }
+
+    // Checks that a number has no leading zeros, if asked to.
+    fn check_digits(&self, token: Token) -> Result<(), Error> {
+        if self.options.leading_zeros {
+            return Ok(());
+        }
+        let text = self.text(token);
+        let digits = text.trim_start_matches(['+', '-']).as_bytes();
+        if digits[0] != b'0' || !matches!(digits.get(1), Some(b'0'..=b'9')) {
+            return Ok(());
+        }
+        let number = text.into();
+        Err(self.error_at(token.lo, token.hi, ErrorKind::LeadingZeros { number }))
+    }
+
+    // Like `scalar`, for `EventReader`.
+    pub(crate) fn borrowed_scalar(&mut self, token: Token) -> Result<BorrowedValue<'a>, Error> {
+        self.scalar(token)
+    }
+
+    pub(crate) fn text(&self, token: Token) -> &'a str {
+        &self.str[token.lo..token.hi]
+    }
+
+    // Reads the value after the `#_` at `lo`, only building it if it has to be
+    // checked for duplicates.
+    pub(crate) fn discard(&mut self, lo: usize) -> Result<(), Error> {
+        let result = if self.options.duplicates {
+            self.read_with::<()>()
+        } else {
+            self.read_with::<BorrowedValue>()
+                .map(|result| result.map(drop))
+        };
+        match result {
+            Some(result) => result,
+            None => Err(self.missing(lo, ErrorKind::MissingDiscardedValue)),
         }
     }
 
-    fn advance_while<F: FnMut(char) -> bool>(&mut self, mut f: F) -> usize {
+    // Decodes the string starting at `lo`. It's borrowed from the input unless
+    // it contains an escape, which `memchr` finds quickly.
+    fn string(&self, lo: usize) -> Result<Cow<'a, str>, Error> {
+        let str = self.str;
+        let bytes = str.as_bytes();
+        let unclosed = || self.error_at(lo, str.len(), ErrorKind::UnclosedString);
+
+        let mut owned: Option<String> = None;
+        let mut chunk = lo + 1;
         loop {
-            match self.chars.clone().next() {
-                Some((pos, ch)) => {
-                    if f(ch) {
-                        self.chars.next();
-                    } else {
-                        return pos;
+            let at = match memchr2(b'"', b'\\', &bytes[chunk..]) {
+                Some(offset) => chunk + offset,
+                None => return Err(unclosed()),
+            };
+            if bytes[at] == b'"' {
+                return Ok(match owned {
+                    Some(mut string) => {
+                        string.push_str(&str[chunk..at]);
+                        Cow::Owned(string)
                     }
+                    None => Cow::Borrowed(&str[chunk..at]),
+                });
+            }
+
+            let string = owned.get_or_insert_with(String::new);
+            string.push_str(&str[chunk..at]);
+            string.push(match bytes.get(at + 1) {
+                Some(b't') => '\t',
+                Some(b'r') => '\r',
+                Some(b'n') => '\n',
+                Some(b'\\') => '\\',
+                Some(b'"') => '"',
+                Some(_) => {
+                    let hi = lexer::char_end(str, at + 1);
+                    let escape = str[at..hi].into();
+                    return Err(self.error_at(at, hi, ErrorKind::InvalidEscape { escape }));
                 }
-                None => return self.str.len(),
+                None => return Err(unclosed()),
+            });
+            chunk = at + 2;
+        }
+    }
+
+    // Checks that no key or element of the map or set opened by `open`, whose
+    // items span `spans`, repeats an earlier one.
+    fn unique<T: Build<'a>>(
+        &self,
+        open: Token,
+        items: &[T],
+        spans: &[(usize, usize)],
+    ) -> Result<(), Error> {
+        if self.options.duplicates {
+            return Ok(());
+        }
+        let step = if open.kind == TokenKind::LeftBrace {
+            2
+        } else {
+            1
+        };
+        match T::repeat(items, step) {
+            Some((first, index)) => {
+                let (lo, hi) = spans[index * step];
+                let (first_lo, first_hi) = spans[first * step];
+                Err(self.repeated(open, lo, hi, first_lo, first_hi))
             }
+            None => Ok(()),
+        }
+    }
+
+    // The error for the item at `lo..hi` of the map or set opened by `open`,
+    // which repeats the one at `first_lo..first_hi`.
+    pub(crate) fn repeated(
+        &self,
+        open: Token,
+        lo: usize,
+        hi: usize,
+        first_lo: usize,
+        first_hi: usize,
+    ) -> Error {
+        let kind = match open.kind {
+            TokenKind::LeftBrace => ErrorKind::DuplicateKey { first_lo, first_hi },
+            _ => ErrorKind::DuplicateElement { first_lo, first_hi },
+        };
+        self.error_at(lo, hi, kind)
+    }
+
+    // The error for an `Error` token, or a token that can't start a value.
+    pub(crate) fn error(&self, token: Token) -> Error {
+        let text = self.text(token);
+        let ch = text.chars().next().unwrap();
+        let kind = match ch {
+            '"' if token.kind == TokenKind::Error => match self.string(token.lo) {
+                Err(err) => return err,
+                Ok(_) => unreachable!(),
+            },
+            '\\' => ErrorKind::InvalidCharLiteral {
+                literal: text.into(),
+            },
+            '#' => ErrorKind::InvalidDispatch {
+                dispatch: text.into(),
+            },
+            ')' | ']' | '}' => ErrorKind::UnexpectedDelimiter { delimiter: ch },
+            ':' => ErrorKind::InvalidSymbol {
+                symbol: text.into(),
+            },
+            '0'..='9' | '+' | '-' | '.' => ErrorKind::InvalidNumber {
+                number: text.into(),
+            },
+            ',' => ErrorKind::NotAllowed { syntax: "commas" },
+            ';' => ErrorKind::NotAllowed { syntax: "comments" },
+            _ => ErrorKind::UnexpectedChar { ch },
+        };
+        self.error_at(token.lo, token.hi, kind)
+    }
+
+    // The error for a collection whose opening delimiter spans `lo..hi`.
+    pub(crate) fn unclosed(&self, lo: usize, hi: usize) -> Error {
+        let open = match &self.str[lo..hi] {
+            "(" => "(",
+            "[" => "[",
+            "{" => "{",
+            _ => "#{",
+        };
+        self.error_at(lo, self.str.len(), ErrorKind::UnclosedDelimiter { open })
+    }
+
+    // The error for a map starting at `lo` and ending before `pos`.
+    pub(crate) fn odd_map(&self, lo: usize) -> Error {
+        self.error_at(lo, self.pos, ErrorKind::OddMapEntries)
+    }
+
+    // The error for a tag or `#_` at `lo` that isn't followed by a value.
+    pub(crate) fn missing(&self, lo: usize, kind: ErrorKind) -> Error {
+        self.error_at(lo, self.str.len(), kind)
+    }
+
+    // The error for `lo..hi` going over `limit`, which is `max`.
+    fn limit(&self, lo: usize, hi: usize, limit: Limit, max: usize) -> Error {
+        self.error_at(lo, hi, ErrorKind::LimitExceeded { limit, max })
+    }
+
+    fn error_at(&self, lo: usize, hi: usize, kind: ErrorKind) -> Error {
+        Error {
+            lo,
+            hi,
+            kind,
+            start: self.locate(lo),
+            end: self.locate(hi),
+            source_name: self.source_name.clone(),
         }
     }
+
+    // The position of `offset`, counted on from the nearest offset before it
+    // whose position is known, so that the many errors of
+    // `Document::parse_recovering` don't each rescan the input.
+    fn locate(&self, offset: usize) -> Position {
+        let mut positions = self.positions.borrow_mut();
+        let (from, mut position) = match positions.range(..=offset).next_back() {
+            Some((&from, &position)) => (from, position),
+            None => (0, Position::start()),
+        };
+        position.advance(&self.str.as_bytes()[from..offset]);
+        positions.insert(offset, position);
+        position
+    }
+
+    #[cfg(not(feature = "intern"))]
+    fn name(&mut self, name: &str) -> Name {
+        name.into()
+    }
+
+    #[cfg(feature = "intern")]
+    fn name(&mut self, name: &str) -> Name {
+        self.interner.intern(name)
+    }
 }
 
-fn is_symbol_head(ch: char) -> bool {
-    match ch {
-        'a'...'z'
-        | 'A'...'Z'
-        | '.'
-        | '*'
-        | '+'
-        | '!'
-        | '-'
-        | '_'
-        | '?'
-        | '$'
-        | '%'
-        | '&'
-        | '='
-        | '<'
-        | '>' => true,
-        _ => false,
+impl ParserOptions {
+    pub fn new() -> ParserOptions {
+        ParserOptions {
+            duplicates: false,
+            comments: true,
+            commas: true,
+            discard: true,
+            tagged: true,
+            invalid_symbols: false,
+            unknown_tags: true,
+            tags: vec![],
+            leading_zeros: false,
+            clojure_numbers: false,
+            symbolic_floats: false,
+            overflow: Overflow::Error,
+            max_depth: 1024,
+            max_values: usize::MAX,
+            max_string_length: usize::MAX,
+            max_collection_length: usize::MAX,
+            max_input_size: usize::MAX,
+        }
+    }
+
+    /// Only what the spec allows, so also no tags other than `inst`, `uuid`
+    /// and those given to `tag`.
+    pub fn strict() -> ParserOptions {
+        ParserOptions::new().allow_unknown_tags(false)
+    }
+
+    /// Also what Clojure reads or prints that isn't EDN: repeated map keys and
+    /// set elements, of which the last is kept, symbols and keywords like
+    /// `a/b/c` or `::a`, Clojure's integers and `##Inf`, `##-Inf` and `##NaN`.
+    pub fn lenient() -> ParserOptions {
+        ParserOptions::new()
+            .allow_duplicates(true)
+            .allow_invalid_symbols(true)
+            .allow_clojure_numbers(true)
+            .allow_symbolic_floats(true)
+    }
+
+    /// Whether a map key or set element can be repeated, keeping the last one,
+    /// instead of being an `ErrorKind::DuplicateKey` or `DuplicateElement`.
+    pub fn allow_duplicates(mut self, allow: bool) -> ParserOptions {
+        self.duplicates = allow;
+        self
+    }
+
+    /// Whether `;` comments are allowed.
+    pub fn allow_comments(mut self, allow: bool) -> ParserOptions {
+        self.comments = allow;
+        self
+    }
+
+    /// Whether commas are allowed as whitespace.
+    pub fn allow_commas(mut self, allow: bool) -> ParserOptions {
+        self.commas = allow;
+        self
+    }
+
+    /// Whether values can be discarded with `#_`.
+    pub fn allow_discard(mut self, allow: bool) -> ParserOptions {
+        self.discard = allow;
+        self
+    }
+
+    /// Whether tagged values like `#inst "2017-01-01"` are allowed at all.
+    pub fn allow_tagged(mut self, allow: bool) -> ParserOptions {
+        self.tagged = allow;
+        self
+    }
+
+    /// Whether symbols and keywords the spec doesn't allow, like `a/b/c`,
+    /// `a/` or `::a`, are read as they are. They aren't by default.
+    pub fn allow_invalid_symbols(mut self, allow: bool) -> ParserOptions {
+        self.invalid_symbols = allow;
+        self
+    }
+
+    /// Whether tags other than `inst`, `uuid` and those given to `tag` are
+    /// allowed.
+    pub fn allow_unknown_tags(mut self, allow: bool) -> ParserOptions {
+        self.unknown_tags = allow;
+        self
+    }
+
+    /// Adds a tag that's allowed even if unknown tags aren't.
+    pub fn tag(mut self, tag: &str) -> ParserOptions {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Whether numbers like `007` or `-00.5` are allowed, which they aren't by
+    /// default. In Clojure number mode, an integer like `007` is octal instead.
+    pub fn allow_leading_zeros(mut self, allow: bool) -> ParserOptions {
+        self.leading_zeros = allow;
+        self
+    }
+
+    /// Whether integers are read the way Clojure reads them: `0xFF` in
+    /// hexadecimal, `017` in octal and `2r1010` or `36rZZ` in any radix from
+    /// 2 to 36, and ratios like `22/7` as a `Value::Ratio`. They're an
+    /// `ErrorKind::InvalidNumber` otherwise.
+    pub fn allow_clojure_numbers(mut self, allow: bool) -> ParserOptions {
+        self.clojure_numbers = allow;
+        self
+    }
+
+    /// Whether `##Inf`, `##-Inf` and `##NaN`, which is how `Value` prints
+    /// infinite and NaN floats, are read as floats.
+    pub fn allow_symbolic_floats(mut self, allow: bool) -> ParserOptions {
+        self.symbolic_floats = allow;
+        self
+    }
+
+    /// How integers that don't fit in an `i64` are read.
+    pub fn on_overflow(mut self, overflow: Overflow) -> ParserOptions {
+        self.overflow = overflow;
+        self
+    }
+
+    /// The most collections and tagged values a value can be nested in, 1024
+    /// by default. Nesting doesn't use the call stack while reading, but
+    /// dropping, printing or comparing a `Value` recurses, so a much higher
+    /// limit can overflow it there.
+    pub fn max_depth(mut self, max: usize) -> ParserOptions {
+        self.max_depth = max;
+        self
+    }
+
+    /// The most values a single read can return, counting those nested in it
+    /// and those discarded with `#_`. Unlimited by default.
+    pub fn max_values(mut self, max: usize) -> ParserOptions {
+        self.max_values = max;
+        self
+    }
+
+    /// The most bytes a string, symbol, keyword or tag can be as written,
+    /// leaving out quotes and the `:` or `#` before a keyword or tag.
+    /// Unlimited by default.
+    pub fn max_string_length(mut self, max: usize) -> ParserOptions {
+        self.max_string_length = max;
+        self
+    }
+
+    /// The most items a list, vector or set, or entries a map, can have.
+    /// Unlimited by default.
+    pub fn max_collection_length(mut self, max: usize) -> ParserOptions {
+        self.max_collection_length = max;
+        self
+    }
+
+    /// The most bytes of input, all of which is rejected if it's longer.
+    /// Unlimited by default.
+    pub fn max_input_size(mut self, max: usize) -> ParserOptions {
+        self.max_input_size = max;
+        self
+    }
+}
+
+impl Default for ParserOptions {
+    fn default() -> ParserOptions {
+        ParserOptions::new()
+    }
+}
+
+// The values a `Parser` can build, so that owned and borrowed trees are read by
+// the same code.
+trait Build<'a>: Sized {
+    fn nil() -> Self;
+    fn boolean(b: bool) -> Self;
+    fn string(s: Cow<'a, str>) -> Self;
+    fn char(ch: char) -> Self;
+    fn symbol(parser: &mut Parser<'a>, name: &'a str) -> Self;
+    fn keyword(parser: &mut Parser<'a>, name: &'a str) -> Self;
+    fn integer(i: i64) -> Self;
+    fn float(f: f64) -> Self;
+    fn ratio(r: Ratio) -> Self;
+    fn list(items: Vec<Self>) -> Self;
+    fn vector(items: Vec<Self>) -> Self;
+    // A repeated map key keeps the last value.
+    fn map(entries: Vec<(Self, Self)>) -> Self;
+    fn set(items: Vec<Self>) -> Self;
+    fn tagged(tag: &'a str, value: Self) -> Self;
+
+    // The indices in every `step`th of `items` of the first one that repeats
+    // an earlier one, and of the one it repeats.
+    fn repeat(items: &[Self], step: usize) -> Option<(usize, usize)>;
+
+    // Called with the span of each value once it has been read.
+    #[inline]
+    fn span(self, _lo: usize, _hi: usize) -> Self {
+        self
+    }
+}
+
+impl<'a> Build<'a> for Value {
+    fn nil() -> Self {
+        Value::Nil
+    }
+
+    fn boolean(b: bool) -> Self {
+        Value::Boolean(b)
+    }
+
+    fn string(s: Cow<'a, str>) -> Self {
+        Value::String(s.into_owned())
+    }
+
+    fn char(ch: char) -> Self {
+        Value::Char(ch)
+    }
+
+    fn symbol(parser: &mut Parser<'a>, name: &'a str) -> Self {
+        Value::Symbol(parser.name(name))
+    }
+
+    fn keyword(parser: &mut Parser<'a>, name: &'a str) -> Self {
+        Value::Keyword(parser.name(name))
+    }
+
+    fn integer(i: i64) -> Self {
+        Value::Integer(i)
+    }
+
+    fn float(f: f64) -> Self {
+        Value::Float(OrderedFloat(f))
+    }
+
+    fn ratio(r: Ratio) -> Self {
+        Value::Ratio(r)
+    }
+
+    fn list(items: Vec<Self>) -> Self {
+        Value::List(items.into_iter().collect())
+    }
+
+    fn vector(items: Vec<Self>) -> Self {
+        Value::Vector(items.into_iter().collect())
+    }
+
+    fn map(entries: Vec<(Self, Self)>) -> Self {
+        let mut map = Map::new();
+        for (key, value) in entries {
+            map.insert(key, value);
+        }
+        Value::Map(map)
+    }
+
+    fn set(items: Vec<Self>) -> Self {
+        Value::Set(items.into_iter().collect())
+    }
+
+    fn tagged(tag: &'a str, value: Self) -> Self {
+        Value::Tagged(tag.into(), Box::new(value))
+    }
+
+    fn repeat(items: &[Self], step: usize) -> Option<(usize, usize)> {
+        first_repeat(items.iter().step_by(step))
+    }
+}
+
+// Builds nothing, for reading values that are discarded.
+impl<'a> Build<'a> for () {
+    fn nil() -> Self {}
+
+    fn boolean(_: bool) -> Self {}
+
+    fn string(_: Cow<'a, str>) -> Self {}
+
+    fn char(_: char) -> Self {}
+
+    fn symbol(_: &mut Parser<'a>, _: &'a str) -> Self {}
+
+    fn keyword(_: &mut Parser<'a>, _: &'a str) -> Self {}
+
+    fn integer(_: i64) -> Self {}
+
+    fn float(_: f64) -> Self {}
+
+    fn ratio(_: Ratio) -> Self {}
+
+    fn list(_: Vec<Self>) -> Self {}
+
+    fn vector(_: Vec<Self>) -> Self {}
+
+    fn map(_: Vec<(Self, Self)>) -> Self {}
+
+    fn set(_: Vec<Self>) -> Self {}
+
+    fn tagged(_: &'a str, _: Self) -> Self {}
+
+    fn repeat(_: &[Self], _: usize) -> Option<(usize, usize)> {
+        None
+    }
+}
+
+impl<'a> Build<'a> for BorrowedValue<'a> {
+    fn nil() -> Self {
+        BorrowedValue::Nil
+    }
+
+    fn boolean(b: bool) -> Self {
+        BorrowedValue::Boolean(b)
+    }
+
+    fn string(s: Cow<'a, str>) -> Self {
+        BorrowedValue::String(s)
+    }
+
+    fn char(ch: char) -> Self {
+        BorrowedValue::Char(ch)
+    }
+
+    fn symbol(_: &mut Parser<'a>, name: &'a str) -> Self {
+        BorrowedValue::Symbol(name.into())
+    }
+
+    fn keyword(_: &mut Parser<'a>, name: &'a str) -> Self {
+        BorrowedValue::Keyword(name.into())
+    }
+
+    fn integer(i: i64) -> Self {
+        BorrowedValue::Integer(i)
+    }
+
+    fn float(f: f64) -> Self {
+        BorrowedValue::Float(OrderedFloat(f))
+    }
+
+    fn ratio(r: Ratio) -> Self {
+        BorrowedValue::Ratio(r)
+    }
+
+    fn list(items: Vec<Self>) -> Self {
+        BorrowedValue::List(items)
+    }
+
+    fn vector(items: Vec<Self>) -> Self {
+        BorrowedValue::Vector(items)
+    }
+
+    fn map(entries: Vec<(Self, Self)>) -> Self {
+        BorrowedValue::Map(entries.into_iter().collect::<BTreeMap<_, _>>())
+    }
+
+    fn set(items: Vec<Self>) -> Self {
+        BorrowedValue::Set(items.into_iter().collect::<BTreeSet<_>>())
+    }
+
+    fn tagged(tag: &'a str, value: Self) -> Self {
+        BorrowedValue::Tagged(tag.into(), Box::new(value))
+    }
+
+    fn repeat(items: &[Self], step: usize) -> Option<(usize, usize)> {
+        first_repeat(items.iter().step_by(step))
+    }
+}
+
+// Each node is built with an empty span that `span` then fills in.
+impl<'a> Build<'a> for Spanned<SpannedValue> {
+    fn nil() -> Self {
+        unspanned(SpannedValue::Nil)
+    }
+
+    fn boolean(b: bool) -> Self {
+        unspanned(SpannedValue::Boolean(b))
+    }
+
+    fn string(s: Cow<'a, str>) -> Self {
+        unspanned(SpannedValue::String(s.into_owned()))
+    }
+
+    fn char(ch: char) -> Self {
+        unspanned(SpannedValue::Char(ch))
+    }
+
+    fn symbol(parser: &mut Parser<'a>, name: &'a str) -> Self {
+        unspanned(SpannedValue::Symbol(parser.name(name)))
+    }
+
+    fn keyword(parser: &mut Parser<'a>, name: &'a str) -> Self {
+        unspanned(SpannedValue::Keyword(parser.name(name)))
+    }
+
+    fn integer(i: i64) -> Self {
+        unspanned(SpannedValue::Integer(i))
+    }
+
+    fn float(f: f64) -> Self {
+        unspanned(SpannedValue::Float(OrderedFloat(f)))
+    }
+
+    fn ratio(r: Ratio) -> Self {
+        unspanned(SpannedValue::Ratio(r))
+    }
+
+    fn list(items: Vec<Self>) -> Self {
+        unspanned(SpannedValue::List(items))
+    }
+
+    fn vector(items: Vec<Self>) -> Self {
+        unspanned(SpannedValue::Vector(items))
+    }
+
+    // Duplicates are kept, so they're found by lowering a copy of each key.
+    fn map(entries: Vec<(Self, Self)>) -> Self {
+        unspanned(SpannedValue::Map(entries))
+    }
+
+    fn set(items: Vec<Self>) -> Self {
+        unspanned(SpannedValue::Set(items))
+    }
+
+    fn tagged(tag: &'a str, value: Self) -> Self {
+        unspanned(SpannedValue::Tagged(unspanned(tag.into()), Box::new(value)))
+    }
+
+    // Spans don't matter, so the items are compared as values.
+    fn repeat(items: &[Self], step: usize) -> Option<(usize, usize)> {
+        first_repeat(
+            items
+                .iter()
+                .step_by(step)
+                .map(|item| item.clone().into_value()),
+        )
+    }
+
+    fn span(mut self, lo: usize, hi: usize) -> Self {
+        // A tagged value starts with its tag's `#`.
+        if let SpannedValue::Tagged(ref mut tag, _) = self.value {
+            tag.lo = lo;
+            tag.hi = lo + 1 + tag.value.len();
+        }
+        self.lo = lo;
+        self.hi = hi;
+        self
+    }
+}
+
+// The indices of the first of `items` equal to an earlier one, and of that one.
+fn first_repeat<T: Hash + Eq, I: Iterator<Item = T>>(items: I) -> Option<(usize, usize)> {
+    let mut seen = HashMap::new();
+    for (index, item) in items.enumerate() {
+        match seen.entry(item) {
+            Entry::Occupied(first) => return Some((*first.get(), index)),
+            Entry::Vacant(entry) => {
+                entry.insert(index);
+            }
+        }
     }
+    None
 }
 
-fn is_symbol_tail(ch: char) -> bool {
-    is_symbol_head(ch) || match ch {
-        '0'...'9' | ':' | '#' | '/' => true,
-        _ => false,
+fn unspanned<T>(value: T) -> Spanned<T> {
+    Spanned {
+        value,
+        lo: 0,
+        hi: 0,
     }
 }
@@ -0,0 +1,92 @@
+//! Clojure's ratios, like `22/7`, which `Parser` reads in Clojure number mode.
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// A fraction in lowest terms with a positive denominator, so that equal
+/// ratios have equal numerators and denominators: `-2/6` is `-1/3`.
+///
+/// Both parts are `i64`s, which is enough for the ratios Clojure prints for
+/// most data; ones that don't fit are a `NumberOverflow` error when read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Ratio {
+    numer: i64,
+    denom: i64,
+}
+
+impl Ratio {
+    /// Returns `numer / denom` in lowest terms, or `None` if `denom` is 0 or
+    /// the result doesn't fit, as for `i64::MIN / -1`.
+    ///
+    /// The result is a whole number if `denom()` is 1; `Parser` reads those
+    /// as a `Value::Integer` instead, like Clojure.
+    pub fn new(numer: i64, denom: i64) -> Option<Ratio> {
+        if denom == 0 {
+            return None;
+        }
+        let (mut numer, mut denom) = (i128::from(numer), i128::from(denom));
+        let gcd = gcd(numer.unsigned_abs(), denom.unsigned_abs()) as i128;
+        numer /= gcd;
+        denom /= gcd;
+        if denom < 0 {
+            numer = -numer;
+            denom = -denom;
+        }
+        Some(Ratio {
+            numer: i64::try_from(numer).ok()?,
+            denom: i64::try_from(denom).ok()?,
+        })
+    }
+
+    pub fn numer(&self) -> i64 {
+        self.numer
+    }
+
+    /// Always positive.
+    pub fn denom(&self) -> i64 {
+        self.denom
+    }
+
+    /// The ratio as a float, e.g. `0.5` for `1/2`.
+    pub fn to_f64(&self) -> f64 {
+        self.numer as f64 / self.denom as f64
+    }
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        let rem = a % b;
+        a = b;
+        b = rem;
+    }
+    a
+}
+
+/// Orders ratios by their value.
+impl Ord for Ratio {
+    fn cmp(&self, other: &Ratio) -> Ordering {
+        let a = i128::from(self.numer) * i128::from(other.denom);
+        let b = i128::from(other.numer) * i128::from(self.denom);
+        a.cmp(&b)
+    }
+}
+
+impl PartialOrd for Ratio {
+    fn partial_cmp(&self, other: &Ratio) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Prints the ratio as EDN reads it in Clojure number mode, e.g. `-1/3`.
+impl fmt::Display for Ratio {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.numer, self.denom)
+    }
+}
+
+impl From<Ratio> for f64 {
+    fn from(ratio: Ratio) -> f64 {
+        ratio.to_f64()
+    }
+}
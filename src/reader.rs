@@ -0,0 +1,472 @@
+//! Reading EDN incrementally, from chunks of bytes or from an `io::BufRead`.
+//!
+//! Both readers find where each top-level value ends with a small state
+//! machine that looks at every byte once, and only hand complete values to a
+//! `Parser`, so no input is parsed twice however it's split up.
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+#[cfg(feature = "intern")]
+use std::mem;
+use std::str;
+
+use memchr::{memchr, memchr2};
+
+#[cfg(feature = "intern")]
+use intern::Interner;
+use lexer::is_symbol_head;
+use parser::{Error, ErrorKind, Limit, Parser, ParserOptions, Position};
+use Value;
+
+/// A parser that is fed chunks of input as they arrive, e.g. from a socket.
+///
+/// Values are returned as soon as the bytes that end them have been fed, and
+/// only the value being read is buffered. Errors are reported with byte
+/// offsets from the start of the input.
+pub struct PushParser {
+    // Bytes of a value that hasn't ended yet, starting at `offset` and
+    // `position` in the input. `buf[..scanned]` has already been scanned.
+    buf: Vec<u8>,
+    offset: usize,
+    position: Position,
+    scanned: usize,
+    scanner: Scanner,
+    values: VecDeque<Result<Value, Error>>,
+    source_name: Option<String>,
+    options: ParserOptions,
+    // Whether more than `options.max_input_size` bytes have been fed, after
+    // which the input is ignored.
+    over_limit: bool,
+    #[cfg(feature = "intern")]
+    interner: Interner,
+}
+
+/// The values completed by `PushParser::feed` or `PushParser::finish`.
+///
+/// Values that aren't consumed are returned by the next call.
+pub struct Values<'a> {
+    parser: &'a mut PushParser,
+}
+
+impl<'a> Iterator for Values<'a> {
+    type Item = Result<Value, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.parser.values.pop_front()
+    }
+}
+
+impl Default for PushParser {
+    fn default() -> PushParser {
+        PushParser::new()
+    }
+}
+
+impl PushParser {
+    pub fn new() -> PushParser {
+        PushParser {
+            buf: vec![],
+            offset: 0,
+            position: Position::start(),
+            scanned: 0,
+            scanner: Scanner::new(),
+            values: VecDeque::new(),
+            source_name: None,
+            options: ParserOptions::new(),
+            over_limit: false,
+            #[cfg(feature = "intern")]
+            interner: Interner::new(),
+        }
+    }
+
+    /// Names the input, e.g. with its file path, for the errors it returns.
+    pub fn with_source_name(mut self, name: &str) -> PushParser {
+        self.source_name = Some(name.into());
+        self
+    }
+
+    /// Reads the input with `options`, like `Parser::with_options`.
+    pub fn with_options(mut self, options: ParserOptions) -> PushParser {
+        self.options = options;
+        self
+    }
+
+    /// Creates a parser that interns names into `interner`.
+    #[cfg(feature = "intern")]
+    pub fn with_interner(interner: Interner) -> PushParser {
+        PushParser {
+            interner,
+            ..PushParser::new()
+        }
+    }
+
+    /// Returns the interner holding every name read so far.
+    #[cfg(feature = "intern")]
+    pub fn into_interner(self) -> Interner {
+        self.interner
+    }
+
+    /// Adds `bytes` to the input and returns the values it completed.
+    ///
+    /// Chunks can be split anywhere, even inside a character. Once the input
+    /// is longer than `ParserOptions::max_input_size`, the values before the
+    /// limit are followed by an `ErrorKind::LimitExceeded` error and the rest
+    /// of the input is ignored.
+    pub fn feed(&mut self, bytes: &[u8]) -> Values<'_> {
+        if self.over_limit {
+            return Values { parser: self };
+        }
+        let max = self.options.max_input_size;
+        let room = max.saturating_sub(self.offset + self.buf.len());
+        self.buf.extend_from_slice(&bytes[..bytes.len().min(room)]);
+        self.scan();
+        if bytes.len() > room {
+            let limit = Limit::InputSize;
+            let err = self.error_at_end(ErrorKind::LimitExceeded { limit, max });
+            self.values.push_back(Err(err));
+            self.over_limit = true;
+            self.buf.clear();
+            self.scanned = 0;
+        }
+        Values { parser: self }
+    }
+
+    /// Ends the input, returning the values left in it.
+    ///
+    /// Input that was cut off is reported like `Parser` reports it, e.g. as an
+    /// unclosed collection. The parser can be fed again afterwards.
+    pub fn finish(&mut self) -> Values<'_> {
+        let len = self.buf.len();
+        self.read_values(0, len);
+        self.offset += len;
+        self.position.advance(&self.buf);
+        self.buf.clear();
+        self.scanned = 0;
+        self.scanner = Scanner::new();
+        Values { parser: self }
+    }
+
+    /// Returns whether the input fed so far ends inside a value (or comment),
+    /// so that more input is needed to read it.
+    pub fn needs_more(&self) -> bool {
+        !self.buf.is_empty()
+    }
+
+    // The error `kind` at the end of the input fed so far.
+    fn error_at_end(&self, kind: ErrorKind) -> Error {
+        let len = self.buf.len();
+        self.locate(Error::new(&self.buf, len, len, kind), 0)
+    }
+
+    // Moves an error in `buf[lo..]` to where it is in the whole input.
+    fn locate(&self, err: Error, lo: usize) -> Error {
+        let mut position = self.position;
+        position.advance(&self.buf[..lo]);
+        Error {
+            source_name: self.source_name.clone(),
+            ..err.after(self.offset + lo, position)
+        }
+    }
+
+    // Reads every value that has ended in `buf` and drops it, along with any
+    // whitespace after it.
+    fn scan(&mut self) {
+        let mut start = 0;
+        while let Some(len) = self.scanner.scan(&self.buf[self.scanned..]) {
+            self.scanned += len;
+            let end = self.scanned;
+            self.read_values(start, end);
+            start = end;
+        }
+        self.scanned = self.buf.len();
+        if self.scanner.is_idle() {
+            start = self.buf.len();
+        }
+        self.offset += start;
+        self.position.advance(&self.buf[..start]);
+        self.buf.drain(..start);
+        self.scanned -= start;
+    }
+
+    // Reads the values in `buf[lo..hi]`.
+    fn read_values(&mut self, lo: usize, hi: usize) {
+        match str::from_utf8(&self.buf[lo..hi]) {
+            Ok(str) => {
+                #[cfg(not(feature = "intern"))]
+                let parser = Parser::new(str);
+                #[cfg(feature = "intern")]
+                let parser =
+                    Parser::with_interner(str, mem::replace(&mut self.interner, Interner::new()));
+                let mut parser = parser.with_options(self.options.clone());
+                while let Some(result) = parser.read() {
+                    let result = result.map_err(|err| {
+                        parser.skip_to(err.hi);
+                        self.locate(err, lo)
+                    });
+                    self.values.push_back(result);
+                }
+                #[cfg(feature = "intern")]
+                {
+                    self.interner = parser.into_interner();
+                }
+            }
+            Err(err) => {
+                let at = err.valid_up_to();
+                let end = err.error_len().map_or(hi - lo, |len| at + len);
+                let err = Error::new(&self.buf[lo..hi], at, end, ErrorKind::InvalidUtf8);
+                let err = self.locate(err, lo);
+                self.values.push_back(Err(err));
+            }
+        }
+    }
+}
+
+/// Reads top-level values from an `io::BufRead` one at a time.
+///
+/// This is a `PushParser` fed from `reader`, so only the value being read and
+/// the rest of the last chunk `reader` returned are buffered. An I/O error is
+/// returned as an `Error` at the offset it happened at, after which `read`
+/// returns `None`.
+pub struct ReaderParser<R> {
+    reader: R,
+    parser: PushParser,
+    done: bool,
+}
+
+impl<R: BufRead> ReaderParser<R> {
+    pub fn new(reader: R) -> ReaderParser<R> {
+        ReaderParser::with_parser(reader, PushParser::new())
+    }
+
+    /// Creates a parser that interns names into `interner`.
+    #[cfg(feature = "intern")]
+    pub fn with_interner(reader: R, interner: Interner) -> ReaderParser<R> {
+        ReaderParser::with_parser(reader, PushParser::with_interner(interner))
+    }
+
+    fn with_parser(reader: R, parser: PushParser) -> ReaderParser<R> {
+        ReaderParser {
+            reader,
+            parser,
+            done: false,
+        }
+    }
+
+    /// Names the input, e.g. with its file path, for the errors it returns.
+    pub fn with_source_name(mut self, name: &str) -> ReaderParser<R> {
+        self.parser.source_name = Some(name.into());
+        self
+    }
+
+    /// Reads the input with `options`, like `Parser::with_options`.
+    pub fn with_options(mut self, options: ParserOptions) -> ReaderParser<R> {
+        self.parser.options = options;
+        self
+    }
+
+    /// Returns the interner holding every name read so far.
+    #[cfg(feature = "intern")]
+    pub fn into_interner(self) -> Interner {
+        self.parser.into_interner()
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    pub fn read(&mut self) -> Option<Result<Value, Error>> {
+        loop {
+            if let Some(result) = self.parser.values.pop_front() {
+                return Some(result);
+            }
+            if self.done {
+                return None;
+            }
+
+            let len = match self.reader.fill_buf() {
+                Ok([]) => {
+                    self.done = true;
+                    self.parser.finish();
+                    continue;
+                }
+                Ok(bytes) => {
+                    self.parser.feed(bytes);
+                    self.done = self.parser.over_limit;
+                    bytes.len()
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(self.parser.error_at_end(ErrorKind::Io {
+                        message: err.to_string(),
+                    })));
+                }
+            };
+            self.reader.consume(len);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    // Between values.
+    Space,
+    Comment,
+    String,
+    // After a `\` in a string.
+    Escape,
+    // After the `\` of a character literal.
+    Char,
+    // In a symbol, keyword, number, tag, character literal or invalid token.
+    Token,
+    // After a `#`.
+    Dispatch,
+}
+
+// Finds where top-level values end, following just enough of the grammar to
+// know whether a byte is in a string, a comment, a token or a collection.
+//
+// Tokens only end at ASCII whitespace and delimiters, which can be later than
+// `Parser` ends them, e.g. in `1\u{a0}2`. That's fine, since `Parser` then
+// reads both values from what was scanned.
+struct Scanner {
+    mode: Mode,
+    depth: usize,
+    // Whether the current token is a tag, and whether a top-level tag is still
+    // waiting for its value.
+    tag: bool,
+    tagged: bool,
+    // The number of top-level `#_`s still waiting for the value they discard,
+    // which doesn't end the top-level value.
+    discards: usize,
+}
+
+impl Scanner {
+    fn new() -> Scanner {
+        Scanner {
+            mode: Mode::Space,
+            depth: 0,
+            tag: false,
+            tagged: false,
+            discards: 0,
+        }
+    }
+
+    // Whether nothing but whitespace has been scanned since the last value.
+    fn is_idle(&self) -> bool {
+        self.mode == Mode::Space && self.depth == 0 && !self.tagged && self.discards == 0
+    }
+
+    // Returns the length of the prefix of `bytes` that ends the current
+    // top-level value, or `None` if it doesn't end in `bytes`.
+    fn scan(&mut self, bytes: &[u8]) -> Option<usize> {
+        let mut i = 0;
+        while i < bytes.len() {
+            let byte = bytes[i];
+            match self.mode {
+                Mode::Space => match byte {
+                    b';' => self.mode = Mode::Comment,
+                    b'"' => self.mode = Mode::String,
+                    b'\\' => self.mode = Mode::Char,
+                    b'#' => self.mode = Mode::Dispatch,
+                    b'(' | b'[' | b'{' => self.depth += 1,
+                    b')' | b']' | b'}' => {
+                        // An unmatched closing delimiter is a value of its own
+                        // as far as the scanner is concerned; `Parser` reports
+                        // it as unexpected.
+                        self.depth = self.depth.saturating_sub(1);
+                        if self.end_value() {
+                            return Some(i + 1);
+                        }
+                    }
+                    _ if is_whitespace(byte) => {}
+                    _ => self.mode = Mode::Token,
+                },
+                Mode::Comment => match memchr(b'\n', &bytes[i..]) {
+                    Some(offset) => {
+                        self.mode = Mode::Space;
+                        i += offset;
+                    }
+                    None => return None,
+                },
+                Mode::String => match memchr2(b'"', b'\\', &bytes[i..]) {
+                    Some(offset) => {
+                        i += offset;
+                        if bytes[i] == b'\\' {
+                            self.mode = Mode::Escape;
+                        } else {
+                            self.mode = Mode::Space;
+                            if self.end_value() {
+                                return Some(i + 1);
+                            }
+                        }
+                    }
+                    None => return None,
+                },
+                Mode::Escape => self.mode = Mode::String,
+                // The first character of a literal is always part of it, so
+                // that e.g. `\(` and `\"` work.
+                Mode::Char => self.mode = Mode::Token,
+                Mode::Dispatch => {
+                    if byte == b'{' {
+                        self.mode = Mode::Space;
+                        self.depth += 1;
+                    } else if byte == b'_' {
+                        self.mode = Mode::Space;
+                        if self.depth == 0 {
+                            self.discards += 1;
+                        }
+                    } else {
+                        self.mode = Mode::Token;
+                        // A non-ASCII character may be split across chunks,
+                        // so it's taken to start a tag and left to the parser
+                        // to check.
+                        self.tag = byte >= 0x80 || is_symbol_head(byte as char);
+                        continue;
+                    }
+                }
+                Mode::Token => {
+                    if is_whitespace(byte) || is_delimiter(byte) {
+                        // The byte that ended the token is scanned again.
+                        self.mode = Mode::Space;
+                        if self.tag {
+                            self.tag = false;
+                            self.tagged |= self.depth == 0;
+                        } else if self.end_value() {
+                            return Some(i);
+                        }
+                        continue;
+                    }
+                }
+            }
+            i += 1;
+        }
+        None
+    }
+
+    // Called at the end of each value; returns whether it ended a top-level
+    // one, i.e. wasn't nested or discarded.
+    fn end_value(&mut self) -> bool {
+        if self.depth > 0 {
+            false
+        } else if self.discards > 0 {
+            self.discards -= 1;
+            false
+        } else {
+            self.tagged = false;
+            true
+        }
+    }
+}
+
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\n' | 0x0b | 0x0c | b'\r' | b',')
+}
+
+fn is_delimiter(byte: u8) -> bool {
+    matches!(
+        byte,
+        b'(' | b')' | b'[' | b']' | b'{' | b'}' | b'"' | b';' | b'\\'
+    )
+}
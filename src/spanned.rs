@@ -0,0 +1,77 @@
+use ordered_float::OrderedFloat;
+
+use {Name, Ratio, Value};
+
+/// A value and the bytes `lo..hi` of the input it was read from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spanned<T> {
+    pub value: T,
+    pub lo: usize,
+    pub hi: usize,
+}
+
+/// A `Value` whose every node is `Spanned`, as returned by
+/// `Parser::read_spanned`.
+///
+/// Map entries are kept in the order they were read, and so are repeated keys
+/// if the parser allows them, so that each key and value can be traced back to
+/// the input.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpannedValue {
+    Nil,
+    Boolean(bool),
+    String(String),
+    Char(char),
+    Symbol(Name),
+    Keyword(Name),
+    Integer(i64),
+    Float(OrderedFloat<f64>),
+    Ratio(Ratio),
+    List(Vec<Spanned<SpannedValue>>),
+    Vector(Vec<Spanned<SpannedValue>>),
+    Map(Vec<(Spanned<SpannedValue>, Spanned<SpannedValue>)>),
+    Set(Vec<Spanned<SpannedValue>>),
+    /// A tag, spanning `#` and its name, and the value it applies to.
+    Tagged(Spanned<String>, Box<Spanned<SpannedValue>>),
+}
+
+impl Spanned<SpannedValue> {
+    /// Drops the spans, returning the `Value` that `Parser::read` would have.
+    pub fn into_value(self) -> Value {
+        match self.value {
+            SpannedValue::Nil => Value::Nil,
+            SpannedValue::Boolean(b) => Value::Boolean(b),
+            SpannedValue::String(s) => Value::String(s),
+            SpannedValue::Char(ch) => Value::Char(ch),
+            SpannedValue::Symbol(name) => Value::Symbol(name),
+            SpannedValue::Keyword(name) => Value::Keyword(name),
+            SpannedValue::Integer(i) => Value::Integer(i),
+            SpannedValue::Float(f) => Value::Float(f),
+            SpannedValue::Ratio(r) => Value::Ratio(r),
+            SpannedValue::List(items) => {
+                Value::List(items.into_iter().map(Spanned::into_value).collect())
+            }
+            SpannedValue::Vector(items) => {
+                Value::Vector(items.into_iter().map(Spanned::into_value).collect())
+            }
+            SpannedValue::Map(entries) => Value::Map(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k.into_value(), v.into_value()))
+                    .collect(),
+            ),
+            SpannedValue::Set(items) => {
+                Value::Set(items.into_iter().map(Spanned::into_value).collect())
+            }
+            SpannedValue::Tagged(tag, value) => {
+                Value::Tagged(tag.value, Box::new(value.into_value()))
+            }
+        }
+    }
+}
+
+impl From<Spanned<SpannedValue>> for Value {
+    fn from(value: Spanned<SpannedValue>) -> Self {
+        value.into_value()
+    }
+}
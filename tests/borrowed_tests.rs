@@ -0,0 +1,82 @@
+extern crate edn;
+
+use std::borrow::Cow;
+
+use edn::parser::Parser;
+use edn::BorrowedValue;
+
+fn points_into(s: &str, input: &str) -> bool {
+    let range = input.as_ptr() as usize..input.as_ptr() as usize + input.len();
+    range.contains(&(s.as_ptr() as usize))
+}
+
+#[test]
+fn test_read_borrowed_scalars() {
+    let input = r#"foo/bar :baz "plain" "esc\taped" 42 \c nil"#;
+    let mut parser = Parser::new(input);
+
+    match parser.read_borrowed() {
+        Some(Ok(BorrowedValue::Symbol(Cow::Borrowed(s)))) => {
+            assert_eq!(s, "foo/bar");
+            assert!(points_into(s, input));
+        }
+        otherwise => panic!("unexpected {:?}", otherwise),
+    }
+    match parser.read_borrowed() {
+        Some(Ok(BorrowedValue::Keyword(Cow::Borrowed(s)))) => {
+            assert_eq!(s, "baz");
+            assert!(points_into(s, input));
+        }
+        otherwise => panic!("unexpected {:?}", otherwise),
+    }
+    match parser.read_borrowed() {
+        Some(Ok(BorrowedValue::String(Cow::Borrowed(s)))) => {
+            assert_eq!(s, "plain");
+            assert!(points_into(s, input));
+        }
+        otherwise => panic!("unexpected {:?}", otherwise),
+    }
+    match parser.read_borrowed() {
+        Some(Ok(BorrowedValue::String(Cow::Owned(ref s)))) => assert_eq!(s, "esc\taped"),
+        otherwise => panic!("unexpected {:?}", otherwise),
+    }
+    assert_eq!(parser.read_borrowed(), Some(Ok(BorrowedValue::Integer(42))));
+    assert_eq!(parser.read_borrowed(), Some(Ok(BorrowedValue::Char('c'))));
+    assert_eq!(parser.read_borrowed(), Some(Ok(BorrowedValue::Nil)));
+    assert_eq!(parser.read_borrowed(), None);
+}
+
+#[test]
+fn test_read_borrowed_collections() {
+    let mut parser = Parser::new(r#"#log {:level :info :msg "started" :tags #{"a"} :at [1 2.5]}"#);
+    match parser.read_borrowed() {
+        Some(Ok(BorrowedValue::Tagged(ref tag, ref value))) => {
+            assert_eq!(tag, "log");
+            match **value {
+                BorrowedValue::Map(ref map) => assert_eq!(
+                    map.get(&BorrowedValue::Keyword("msg".into())),
+                    Some(&BorrowedValue::String("started".into()))
+                ),
+                ref otherwise => panic!("unexpected {:?}", otherwise),
+            }
+        }
+        otherwise => panic!("unexpected {:?}", otherwise),
+    }
+}
+
+#[test]
+fn test_into_owned() {
+    let input = r#"(a :b "c\n" #d [1.5 \e] {nil true} #{-1})"#;
+    let borrowed = Parser::new(input).read_borrowed().unwrap().unwrap();
+    let owned = Parser::new(input).read().unwrap().unwrap();
+    assert_eq!(borrowed.into_owned(), owned);
+}
+
+#[test]
+fn test_read_borrowed_errors() {
+    let mut parser = Parser::new("[1 \"2");
+    assert_eq!(
+        parser.read_borrowed().unwrap().unwrap_err(),
+        Parser::new("[1 \"2").read().unwrap().unwrap_err()
+    );
+}
@@ -0,0 +1,112 @@
+extern crate edn;
+
+use edn::parser::Parser;
+use edn::{Ratio, Value};
+
+fn read(str: &str) -> Value {
+    Parser::new(str).read().unwrap().unwrap()
+}
+
+#[test]
+fn test_clojure_eq() {
+    assert!(read("(1 2)").clojure_eq(&read("[1 2]")));
+    assert!(read("[(1 [2]) 3]").clojure_eq(&read("([1 (2)] 3)")));
+    assert!(read("{(1 2) :a}").clojure_eq(&read("{[1 2] :a}")));
+    assert!(read("{:k (1 2)}").clojure_eq(&read("{:k [1 2]}")));
+    assert!(read("#{(1 2) 3}").clojure_eq(&read("#{3 [1 2]}")));
+    assert!(read("#tag (1)").clojure_eq(&read("#tag [1]")));
+    assert!(read("0.0").clojure_eq(&read("-0.0")));
+
+    assert!(!read("1").clojure_eq(&read("1.0")));
+    assert!(!read("(1 2)").clojure_eq(&read("[1 2 3]")));
+    assert!(!read("(1 2)").clojure_eq(&read("#{1 2}")));
+    assert!(!read("{:k (1 2)}").clojure_eq(&read("{:k [2 1]}")));
+    assert!(!read("\"a\"").clojure_eq(&read("\\a")));
+    assert!(!read("#foo 1").clojure_eq(&read("#bar 1")));
+}
+
+#[test]
+fn test_clojure_eq_nan() {
+    let nan = Value::Float(f64::NAN.into());
+    assert_eq!(nan, nan);
+    assert!(!nan.clojure_eq(&nan));
+}
+
+// Expected values are Clojure's `(hash (clojure.edn/read-string input))`.
+#[test]
+fn test_clojure_hash() {
+    let cases = [
+        ("nil", 0),
+        ("true", 1231),
+        ("false", 1237),
+        ("0", 0),
+        ("1", 1392991556),
+        ("\"\"", 0),
+        ("\\a", 97),
+        ("1.0", 1072693248),
+        (":a", -2123407586),
+        ("[]", -2017569654),
+        ("()", -2017569654),
+        ("{}", -15128758),
+        ("#{}", -15128758),
+        ("[1 2 3]", 736442005),
+        ("(1 2 3)", 736442005),
+        ("#tag :a", 1922469188),
+        ("#tag [1 :a]", 1922470180),
+        ("#my/tag :ns/k", 1922368443),
+        ("#tag {:a :b}", 908560608),
+    ];
+    for &(input, hash) in cases.iter() {
+        assert_eq!(read(input).clojure_hash(), hash, "(hash {})", input);
+    }
+}
+
+// A ratio hashes like `clojure.lang.Ratio`, whose numerator and denominator
+// are `BigInteger`s.
+#[test]
+fn test_clojure_hash_ratios() {
+    let cases = [
+        (1, 2, 3),
+        (22, 7, 17),
+        (-1, 3, -4),
+        (1, 1 << 32, 30),
+        (-5, (1 << 32) + 1, -37),
+    ];
+    for &(numer, denom, hash) in cases.iter() {
+        let ratio = Value::Ratio(Ratio::new(numer, denom).unwrap());
+        assert_eq!(ratio.clojure_hash(), hash, "(hash {})", ratio);
+    }
+    let half = Value::Ratio(Ratio::new(1, 2).unwrap());
+    assert!(half.clojure_eq(&half));
+    assert!(!half.clojure_eq(&Value::from(0.5)));
+}
+
+#[test]
+fn test_clojure_hash_agrees_with_clojure_eq() {
+    let pairs = [
+        ("[1 [2 (3)]]", "(1 (2 [3]))"),
+        ("{[1 2] (:a)}", "{(1 2) [:a]}"),
+        ("#{[1] (2)}", "#{(1) [2]}"),
+        ("#tag (1)", "#tag [1]"),
+    ];
+    for &(a, b) in pairs.iter() {
+        assert!(read(a).clojure_eq(&read(b)));
+        assert_eq!(read(a).clojure_hash(), read(b).clojure_hash());
+    }
+}
+
+#[test]
+fn test_clojure_hash_is_order_insensitive() {
+    assert_eq!(
+        read("{:a 1 :b 2 :c 3}").clojure_hash(),
+        read("{:c 3 :a 1 :b 2}").clojure_hash()
+    );
+    assert_eq!(
+        read("#{1 2 3}").clojure_hash(),
+        read("#{3 2 1}").clojure_hash()
+    );
+    assert_ne!(
+        read("[1 2 3]").clojure_hash(),
+        read("[3 2 1]").clojure_hash()
+    );
+}
@@ -0,0 +1,135 @@
+//! Cases from each section of the EDN spec, read from the fixtures in
+//! `tests/conformance`, one file per section. A case is a block of lines:
+//!
+//! ```text
+//! ; A comment about the case.
+//! input: [1 2
+//! input:  3]
+//! value: [1 2 3]
+//! ```
+//!
+//! `input` lines are joined with newlines to make the input. Each `value` is
+//! what the next value read prints as with `Display`, and `error`, if there is
+//! one, the `ErrorKind` variant of the error read after them; the input must
+//! have nothing else in it. A case the crate doesn't pass yet has an
+//! `unsupported` line saying why. Cases are separated by blank lines. Maps and
+//! sets are written in the order they print in, which is the same with or
+//! without the `preserve_order` feature.
+//!
+//! Run `cargo test --test conformance_tests -- --nocapture` to see which cases
+//! pass.
+
+extern crate edn;
+
+use std::fs;
+use std::path::Path;
+
+use edn::parser::Parser;
+
+struct Case {
+    line: usize,
+    input: String,
+    values: Vec<String>,
+    error: Option<String>,
+    unsupported: Option<String>,
+}
+
+fn cases(fixture: &str) -> Vec<Case> {
+    let mut cases = vec![];
+    let mut case: Option<Case> = None;
+    for (i, line) in fixture.lines().chain(Some("")).enumerate() {
+        if line.trim().is_empty() {
+            cases.extend(case.take());
+            continue;
+        }
+        if line.starts_with(';') {
+            continue;
+        }
+        let (key, value) = match line.find(": ") {
+            Some(at) => (&line[..at], &line[at + 2..]),
+            None => (line.trim_end_matches(':'), ""),
+        };
+        let case = case.get_or_insert_with(|| Case {
+            line: i + 1,
+            input: String::new(),
+            values: vec![],
+            error: None,
+            unsupported: None,
+        });
+        match key {
+            "input" if case.line == i + 1 => case.input.push_str(value),
+            "input" => {
+                case.input.push('\n');
+                case.input.push_str(value);
+            }
+            "value" => case.values.push(value.into()),
+            "error" => case.error = Some(value.into()),
+            "unsupported" => case.unsupported = Some(value.into()),
+            _ => panic!("line {}: unknown key {:?}", i + 1, key),
+        }
+    }
+    cases
+}
+
+// Whether reading `case.input` gives what the case expects, or what it gives
+// instead if not.
+fn check(case: &Case) -> Result<(), String> {
+    let mut parser = Parser::new(&case.input);
+    let mut values = vec![];
+    let mut error = None;
+    while let Some(result) = parser.read() {
+        match result {
+            Ok(value) => values.push(value.to_string()),
+            Err(err) => {
+                let kind = format!("{:?}", err.kind);
+                let end = kind.find([' ', '{']).unwrap_or(kind.len());
+                error = Some(kind[..end].to_string());
+                break;
+            }
+        }
+    }
+    if values == case.values && error == case.error {
+        Ok(())
+    } else {
+        Err(format!("read {:?} and error {:?}", values, error))
+    }
+}
+
+#[test]
+fn test_conformance() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/conformance");
+    let mut paths: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+
+    let mut failures = vec![];
+    let (mut passed, mut total) = (0, 0);
+    for path in paths {
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let cases = cases(&fs::read_to_string(&path).unwrap());
+        let mut section_passed = 0;
+        for case in &cases {
+            let at = format!(
+                "{}:{}",
+                path.file_name().unwrap().to_string_lossy(),
+                case.line
+            );
+            match (check(case), &case.unsupported) {
+                (Ok(()), None) => section_passed += 1,
+                (Ok(()), Some(_)) => failures.push(format!(
+                    "{}: {:?} passes but is marked unsupported",
+                    at, case.input
+                )),
+                (Err(_), Some(why)) => println!("  unsupported {}: {:?}: {}", at, case.input, why),
+                (Err(got), None) => failures.push(format!("{}: {:?}: {}", at, case.input, got)),
+            }
+        }
+        println!("{}: {}/{} cases pass", name, section_passed, cases.len());
+        passed += section_passed;
+        total += cases.len();
+    }
+    println!("total: {}/{} cases pass", passed, total);
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
@@ -0,0 +1,425 @@
+extern crate edn;
+
+use edn::cst::{Document, Node};
+use edn::lexer::TokenKind;
+use edn::parser::Parser;
+use edn::parser::{ErrorKind, Limit};
+use edn::Value;
+
+const INPUT: &str = r#"; config
+{:name "esc\"aped\n" , :port +80 ; the port
+ :ratio +1.50   :tags #{:a,:b}
+ #_ :removed #_ #_ 1 2
+ :at #inst ;; when
+   "2017-01-01"
+ :chars [\newline \λ \,]}
+(  )	#_[] x
+"#;
+
+fn read_all(input: &str) -> Result<Vec<Value>, edn::parser::Error> {
+    let mut parser = Parser::new(input);
+    let mut values = vec![];
+    while let Some(value) = parser.read() {
+        values.push(value?);
+    }
+    Ok(values)
+}
+
+#[test]
+fn test_print_is_lossless() {
+    let document = Document::parse(INPUT).unwrap();
+    assert_eq!(document.to_string(), INPUT);
+    for &input in &["", "  ", "; just a comment", "1,2,,3", "#a\n#b\n[]"] {
+        assert_eq!(Document::parse(input).unwrap().to_string(), input);
+    }
+}
+
+#[test]
+fn test_lower_to_values() {
+    let document = Document::parse(INPUT).unwrap();
+    assert_eq!(document.values(), read_all(INPUT).unwrap());
+}
+
+#[test]
+fn test_nodes() {
+    let document = Document::parse("[1 #_ 2, ; c\n 3]").unwrap();
+    assert_eq!(document.nodes.len(), 1);
+    let collection = match document.nodes[0] {
+        Node::Collection(ref collection) => collection,
+        ref otherwise => panic!("unexpected {:?}", otherwise),
+    };
+    assert_eq!(collection.open.kind, TokenKind::LeftBracket);
+    let kinds: Vec<_> = collection
+        .children
+        .iter()
+        .map(|node| match *node {
+            Node::Leaf(ref leaf) => format!("{:?} {:?}", leaf.kind, leaf.text),
+            Node::Discard(_) => format!("Discard {:?}", node.to_string()),
+            ref otherwise => panic!("unexpected {:?}", otherwise),
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            "Integer \"1\"",
+            "Whitespace \" \"",
+            "Discard \"#_ 2\"",
+            "Comma \",\"",
+            "Whitespace \" \"",
+            "Comment \"; c\"",
+            "Whitespace \"\\n \"",
+            "Integer \"3\"",
+        ]
+    );
+    assert_eq!(collection.children[2].to_value(), None);
+}
+
+#[test]
+fn test_errors_match_parser() {
+    for &input in &[
+        "[1 2",
+        "(1 ]",
+        ")",
+        "{:a 1 :b}",
+        "#inst",
+        "[#_]",
+        "#tag )",
+        "\"bad \\x\"",
+        "\"unclosed",
+        "\\foo",
+        "#!",
+        "1 2 {:a}",
+        "{:a 1 :b 2 :a 3}",
+        "#{[1 2] #_ 3 [1, 2]}",
+        "[#_ {#x 1 2 #x 1 3}]",
+    ] {
+        assert_eq!(
+            Document::parse(input),
+            Err(read_all(input).unwrap_err()),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_deep_nesting() {
+    let depth = ErrorKind::LimitExceeded {
+        limit: Limit::Depth,
+        max: 1024,
+    };
+    for open in &["[", "#a ", "#_ "] {
+        let input = open.repeat(1_000_000);
+        let err = Document::parse(&input).unwrap_err();
+        assert_eq!(err.kind, depth);
+        assert_eq!(err.lo, 1024 * open.len());
+        if *open != "#_ " {
+            assert_eq!(Err(err), read_all(&input));
+        }
+
+        let (document, errors) = Document::parse_recovering(&input);
+        assert_eq!(errors[0].kind, depth);
+        assert_eq!(document.to_string(), input);
+    }
+
+    let input = format!("{}{}", "[".repeat(1024), "]".repeat(1024));
+    assert!(Document::parse(&input).is_ok());
+    let input = format!("[{}{} 1]", "(".repeat(1024), ")".repeat(1024));
+    let (document, errors) = recover(&input);
+    assert_eq!(errors.len(), 1);
+    let shallower = format!("[{}{} 1]", "(".repeat(1023), ")".repeat(1023));
+    assert_eq!(document.values(), read_all(&shallower).unwrap());
+}
+
+const CONFIG: &str = r#"; Deployed by CI.
+{:app {:name "web"
+       :version "1.2.3" ; bumped on release
+       :ports [80 443]}
+ :env :prod ; or :staging
+ :debug false}
+"#;
+
+fn kw(name: &str) -> Value {
+    Value::Keyword(name.into())
+}
+
+#[test]
+fn test_get_in() {
+    let document = Document::parse(CONFIG).unwrap();
+    let node = document.get_in(&[kw("app"), kw("ports"), Value::from(1)]);
+    assert_eq!(node.map(|node| node.to_string()), Some("443".into()));
+    assert_eq!(document.get_in(&[kw("app"), kw("missing")]), None);
+    assert_eq!(document.get_in(&[kw("env"), kw("x")]), None);
+
+    let document = Document::parse("#config {:a #b [1]}").unwrap();
+    let node = document.get_in(&[kw("a"), Value::from(0)]);
+    assert_eq!(node.and_then(Node::to_value), Some(Value::from(1)));
+}
+
+#[test]
+fn test_assoc_in_preserves_formatting() {
+    let mut document = Document::parse(CONFIG).unwrap();
+    document
+        .update_in(&[kw("app"), kw("version")], |version| {
+            assert_eq!(version, Value::from("1.2.3"));
+            Value::from("1.2.4")
+        })
+        .unwrap();
+    document
+        .assoc_in(&[kw("app"), kw("ports"), Value::from(2)], Value::from(8080))
+        .unwrap();
+    document
+        .assoc_in(&[kw("app"), kw("owner")], Value::from("ops"))
+        .unwrap();
+    document
+        .assoc_in(&[kw("limits"), kw("cpu")], Value::from(2))
+        .unwrap();
+    assert_eq!(
+        document.to_string(),
+        r#"; Deployed by CI.
+{:app {:name "web"
+       :version "1.2.4" ; bumped on release
+       :ports [80 443 8080]
+       :owner "ops"}
+ :env :prod ; or :staging
+ :debug false
+ :limits {:cpu 2}}
+"#
+    );
+
+    let mut document = Document::parse("{}").unwrap();
+    document.assoc_in(&[kw("a")], Value::from(1)).unwrap();
+    assert_eq!(document.to_string(), "{:a 1}");
+    document
+        .update_in(&[kw("b")], |b| {
+            assert_eq!(b, Value::Nil);
+            Value::from(true)
+        })
+        .unwrap();
+    assert_eq!(document.to_string(), "{:a 1 :b true}");
+}
+
+#[test]
+fn test_dissoc() {
+    let mut document = Document::parse(CONFIG).unwrap();
+    document.dissoc(&[kw("debug")]).unwrap();
+    document.dissoc(&[kw("app"), kw("name")]).unwrap();
+    document.dissoc(&[kw("missing"), kw("x")]).unwrap();
+    assert_eq!(
+        document.to_string(),
+        r#"; Deployed by CI.
+{:app {:version "1.2.3" ; bumped on release
+       :ports [80 443]}
+ :env :prod ; or :staging
+ }
+"#
+    );
+    assert_eq!(document.values(), read_all(&document.to_string()).unwrap());
+
+    let mut document = Document::parse("{:a 1, :b 2, :c 3}").unwrap();
+    document.dissoc(&[kw("b")]).unwrap();
+    assert_eq!(document.to_string(), "{:a 1, :c 3}");
+    document.dissoc(&[kw("c")]).unwrap();
+    assert_eq!(document.to_string(), "{:a 1}");
+}
+
+#[test]
+fn test_edit_errors() {
+    let mut document = Document::parse("{:a [1 2] :b #{}}").unwrap();
+    let error = document
+        .assoc_in(&[kw("a"), Value::from(5)], Value::Nil)
+        .unwrap_err();
+    assert_eq!(error.depth, 1);
+    let error = document
+        .assoc_in(&[kw("b"), kw("x")], Value::Nil)
+        .unwrap_err();
+    assert_eq!(error.depth, 1);
+    let error = document.dissoc(&[kw("a"), Value::from(0)]).unwrap_err();
+    assert_eq!(error.depth, 1);
+    assert!(Document::parse("; empty")
+        .unwrap()
+        .assoc_in(&[kw("a")], Value::Nil)
+        .is_err());
+    assert_eq!(document.to_string(), "{:a [1 2] :b #{}}");
+
+    // Values that don't print as a single value that reads back as them.
+    for value in &[
+        Value::Symbol("".into()),
+        Value::Symbol("x y".into()),
+        Value::Symbol("1".into()),
+        Value::Symbol("; x".into()),
+        Value::from(vec![Value::Symbol("".into())]),
+    ] {
+        let error = document.assoc_in(&[kw("c")], value.clone()).unwrap_err();
+        assert_eq!(error.depth, 0);
+        let error = document.assoc_in(&[kw("a"), Value::from(0)], value.clone());
+        assert_eq!(error.unwrap_err().depth, 2);
+    }
+    assert_eq!(document.to_string(), "{:a [1 2] :b #{}}");
+}
+
+fn recover(input: &str) -> (Document, Vec<(usize, usize, ErrorKind)>) {
+    let (document, errors) = Document::parse_recovering(input);
+    assert_eq!(document.to_string(), input);
+    match Document::parse(input) {
+        Ok(_) => assert!(errors.is_empty()),
+        Err(err) => assert_eq!(errors[0], err),
+    }
+    let errors = errors
+        .into_iter()
+        .map(|err| (err.lo, err.hi, err.kind))
+        .collect();
+    (document, errors)
+}
+
+#[test]
+fn test_parse_recovering() {
+    let (document, errors) = recover(INPUT);
+    assert!(errors.is_empty());
+    assert_eq!(document, Document::parse(INPUT).unwrap());
+
+    let input = "{:a [1 @ 2 (3 ]\n :b #: 99999999999999999999 :c :e}\n] :d";
+    let (document, errors) = recover(input);
+    assert_eq!(
+        errors,
+        vec![
+            (7, 8, ErrorKind::UnexpectedChar { ch: '@' }),
+            (14, 15, ErrorKind::UnexpectedDelimiter { delimiter: ']' }),
+            (
+                20,
+                22,
+                ErrorKind::InvalidDispatch {
+                    dispatch: "#:".into()
+                }
+            ),
+            (
+                23,
+                43,
+                ErrorKind::NumberOverflow {
+                    number: "99999999999999999999".into()
+                }
+            ),
+            (0, 50, ErrorKind::OddMapEntries),
+            (51, 52, ErrorKind::UnexpectedDelimiter { delimiter: ']' }),
+        ]
+    );
+    assert_eq!(
+        document.values(),
+        vec![
+            read_all("{:a [1 2 (3)] :b :c}").unwrap().remove(0),
+            Value::Keyword("d".into()),
+        ]
+    );
+    match document.nodes[0] {
+        Node::Collection(ref map) => {
+            assert!(map.close.is_some());
+            assert!(map.children.iter().any(|node| match *node {
+                Node::Error(ref leaf) => leaf.text == "#:",
+                _ => false,
+            }));
+        }
+        ref otherwise => panic!("unexpected {:?}", otherwise),
+    }
+}
+
+#[test]
+fn test_parse_recovering_at_eof() {
+    let (document, errors) = recover("[1 {:a #x");
+    assert_eq!(
+        errors,
+        vec![
+            (8, 9, ErrorKind::MissingTaggedValue),
+            (3, 9, ErrorKind::UnclosedDelimiter { open: "{" }),
+            (0, 9, ErrorKind::UnclosedDelimiter { open: "[" }),
+        ]
+    );
+    assert_eq!(document.values(), read_all("[1 {}]").unwrap());
+
+    let (document, errors) = recover("(#_ ) \"abc");
+    assert_eq!(
+        errors,
+        vec![
+            (4, 5, ErrorKind::UnexpectedDelimiter { delimiter: ')' }),
+            (6, 10, ErrorKind::UnclosedString),
+        ]
+    );
+    assert_eq!(document.values(), read_all("()").unwrap());
+}
+
+#[test]
+fn test_parse_recovering_positions() {
+    let input = "[1\n (é @\n  {:a} @";
+    let (_, errors) = Document::parse_recovering(input);
+    let positions: Vec<_> = errors
+        .iter()
+        .map(|err| {
+            (
+                (err.start.line, err.start.column),
+                (err.end.line, err.end.column),
+            )
+        })
+        .collect();
+    assert_eq!(
+        positions,
+        vec![
+            ((2, 5), (2, 6)),
+            ((3, 3), (3, 7)),
+            ((3, 8), (3, 9)),
+            ((2, 2), (3, 9)),
+            ((1, 1), (3, 9)),
+        ]
+    );
+}
+
+#[test]
+fn test_odd_maps_from_recovering() {
+    let (mut document, _) = Document::parse_recovering("{:a}");
+    assert_eq!(document.get_in(&[kw("a")]), None);
+    let error = document.assoc_in(&[kw("b")], Value::from(1)).unwrap_err();
+    assert_eq!(error.depth, 0);
+    assert!(document.dissoc(&[kw("a")]).is_err());
+    assert_eq!(document.to_string(), "{:a}");
+
+    let (mut document, _) = Document::parse_recovering("{:a 1 :b}");
+    let node = document.get_in(&[kw("a")]);
+    assert_eq!(node.and_then(Node::to_value), Some(Value::from(1)));
+    assert_eq!(document.get_in(&[kw("b")]), None);
+    assert!(document.assoc_in(&[kw("c")], Value::from(1)).is_err());
+    assert!(document.assoc_in(&[kw("a")], Value::from(2)).is_err());
+    assert!(document.update_in(&[kw("a")], |_| Value::Nil).is_err());
+    assert!(document.dissoc(&[kw("a")]).is_err());
+    assert_eq!(document.to_string(), "{:a 1 :b}");
+
+    let (mut document, _) = Document::parse_recovering("[{:a 1 :b}]");
+    let error = document
+        .assoc_in(&[Value::from(0), kw("c")], Value::from(1))
+        .unwrap_err();
+    assert_eq!(error.depth, 1);
+    let error = document
+        .dissoc(&[Value::from(0), kw("x"), kw("y")])
+        .unwrap_err();
+    assert_eq!(error.depth, 1);
+}
+
+#[test]
+fn test_valueless_tags_from_recovering() {
+    for input in &["#foo", "#foo ", "#a #b"] {
+        let (mut document, _) = Document::parse_recovering(input);
+        assert_eq!(document.get_in(&[kw("a")]), None);
+        let error = document.assoc_in(&[kw("a")], Value::from(1)).unwrap_err();
+        assert_eq!(error.depth, 0);
+        let error = document.dissoc(&[kw("a")]).unwrap_err();
+        assert_eq!(error.depth, 0);
+        assert_eq!(document.to_string(), *input);
+    }
+
+    let (mut document, _) = Document::parse_recovering("{:a #foo}");
+    assert_eq!(document.get_in(&[kw("a"), kw("b")]), None);
+    let error = document
+        .assoc_in(&[kw("a"), kw("b")], Value::from(1))
+        .unwrap_err();
+    assert_eq!(error.depth, 1);
+    let error = document.dissoc(&[kw("a"), kw("b")]).unwrap_err();
+    assert_eq!(error.depth, 1);
+    assert_eq!(document.to_string(), "{:a #foo}");
+}
@@ -0,0 +1,120 @@
+extern crate edn;
+
+use edn::diagnostic::Diagnostic;
+use edn::parser::{Error, Parser};
+
+fn first_error(input: &str) -> Error {
+    let mut parser = Parser::new(input).with_source_name("config.edn");
+    loop {
+        match parser.read() {
+            Some(Ok(_)) => {}
+            Some(Err(err)) => return err,
+            None => panic!("no error in {:?}", input),
+        }
+    }
+}
+
+fn render(input: &str) -> String {
+    Diagnostic::new(&first_error(input), input).to_string()
+}
+
+#[test]
+fn test_render() {
+    assert_eq!(
+        render("[1 2\n \"x\\qy\"]"),
+        "\
+error: invalid string escape `\\q`
+ --> config.edn:2:4
+  |
+2 |  \"x\\qy\"]
+  |    ^^
+"
+    );
+}
+
+#[test]
+fn test_render_unclosed() {
+    assert_eq!(
+        render("{:port 80\n :host \"example.com\"\n"),
+        "\
+error: unclosed `{`
+ --> config.edn:1:1
+  |
+1 | {:port 80
+  | ^ unclosed delimiter
+2 |  :host \"example.com\"
+  |                     - the input ends here
+"
+    );
+
+    let mut input = String::from("\t#{:a");
+    for _ in 0..10 {
+        input.push_str("\n  :b");
+    }
+    assert_eq!(
+        render(&input),
+        "\
+error: unclosed `#{`
+  --> config.edn:1:2
+   |
+ 1 | \t#{:a
+   | \t^^ unclosed delimiter
+...
+11 |   :b
+   |     - the input ends here
+"
+    );
+
+    assert_eq!(
+        render("[:a #inst"),
+        "\
+error: malformed tagged value
+ --> config.edn:1:6
+  |
+1 | [:a #inst
+  |      ^^^^ expected a value after this
+  |          - the input ends here
+"
+    );
+}
+
+#[test]
+fn test_render_color() {
+    let input = "(1 ]";
+    let err = first_error(input);
+    let rendered = Diagnostic::new(&err, input).with_color(true).to_string();
+    assert_eq!(
+        rendered,
+        "\x1b[1;31merror\x1b[0m\x1b[1m: unexpected `]`\x1b[0m\n \
+         \x1b[1;34m-->\x1b[0m config.edn:1:4\n\
+         \x1b[1;34m  |\x1b[0m\n\
+         \x1b[1;34m1 |\x1b[0m (1 ]\n\
+         \x1b[1;34m  |\x1b[0m    \x1b[1;31m^\x1b[0m\n"
+    );
+}
+
+#[test]
+fn test_render_without_source() {
+    let err = first_error("[1 2 3 4 5 6 7 8 9 0 )");
+    assert_eq!(
+        Diagnostic::new(&err, "").to_string(),
+        "error: unexpected `)`\n --> config.edn:1:22\n"
+    );
+}
+
+#[test]
+fn test_render_duplicate() {
+    assert_eq!(
+        render("{:host \"a\"\n :port 80\n :host \"b\"}"),
+        "\
+error: duplicate map key
+ --> config.edn:3:2
+  |
+1 | {:host \"a\"
+  |  ----- first used here
+...
+3 |  :host \"b\"}
+  |  ^^^^^ used again here
+"
+    );
+}
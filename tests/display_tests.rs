@@ -0,0 +1,79 @@
+extern crate edn;
+extern crate ordered_float;
+
+use edn::parser::{Parser, ParserOptions};
+use edn::{Ratio, Value};
+use ordered_float::OrderedFloat;
+
+#[test]
+fn test_display_scalars() {
+    assert_eq!(Value::Nil.to_string(), "nil");
+    assert_eq!(Value::Boolean(true).to_string(), "true");
+    assert_eq!(Value::Integer(-42).to_string(), "-42");
+    assert_eq!(Value::Symbol("foo/bar".into()).to_string(), "foo/bar");
+    assert_eq!(Value::Keyword("foo".into()).to_string(), ":foo");
+    assert_eq!(
+        Value::String("a\t\r\n\\\"b".into()).to_string(),
+        r#""a\t\r\n\\\"b""#
+    );
+    assert_eq!(Value::Char('a').to_string(), "\\a");
+    assert_eq!(Value::Char('\n').to_string(), "\\newline");
+    assert_eq!(Value::Char(' ').to_string(), "\\space");
+    assert_eq!(Value::Char('\u{a0}').to_string(), "\\u00a0");
+    assert_eq!(Value::Char('\u{2028}').to_string(), "\\u2028");
+}
+
+#[test]
+fn test_display_floats() {
+    assert_eq!(Value::Float(OrderedFloat(1.0)).to_string(), "1.0");
+    assert_eq!(Value::Float(OrderedFloat(-1.25)).to_string(), "-1.25");
+    assert_eq!(Value::Float(OrderedFloat(1e300)).to_string(), "1e300");
+    assert_eq!(Value::Float(OrderedFloat(f64::NAN)).to_string(), "##NaN");
+    assert_eq!(
+        Value::Float(OrderedFloat(f64::NEG_INFINITY)).to_string(),
+        "##-Inf"
+    );
+}
+
+#[test]
+fn test_display_collections() {
+    let mut parser =
+        Parser::new(r#"(1 "two" [:three \4]) {:a 1 :b {:c #{nil}}} #inst "2018-01-01" []"#);
+    let printed = (0..4)
+        .map(|_| parser.read().unwrap().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        printed,
+        vec![
+            r#"(1 "two" [:three \4])"#,
+            "{:a 1, :b {:c #{nil}}}",
+            r#"#inst "2018-01-01""#,
+            "[]",
+        ]
+    );
+}
+
+#[test]
+fn test_display_round_trip() {
+    let str = r#"(defn sum [xs] (reduce + 0 xs)) {"k" \space, :f -0.5} #{1 2 3} [\u00a0]"#;
+    let mut parser = Parser::new(str);
+    while let Some(value) = parser.read() {
+        let value = value.unwrap();
+        let printed = value.to_string();
+        assert_eq!(Parser::new(&printed).read(), Some(Ok(value)));
+    }
+}
+
+#[test]
+fn test_display_ratios() {
+    let options = ParserOptions::new().allow_clojure_numbers(true);
+    for &str in &["22/7", "-1/3", "[1/2 {3/4 -5/6}]"] {
+        let value = Parser::new(str)
+            .with_options(options.clone())
+            .read()
+            .unwrap()
+            .unwrap();
+        assert_eq!(value.to_string(), str);
+    }
+    assert_eq!(Value::from(Ratio::new(2, -6).unwrap()).to_string(), "-1/3");
+}
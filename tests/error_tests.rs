@@ -0,0 +1,182 @@
+extern crate edn;
+
+use std::error;
+use std::io::BufReader;
+
+use edn::parser::{Error, ErrorKind, Parser, Position};
+use edn::reader::{PushParser, ReaderParser};
+
+fn first_error(input: &str) -> Error {
+    let mut parser = Parser::new(input);
+    loop {
+        match parser.read() {
+            Some(Ok(_)) => {}
+            Some(Err(err)) => return err,
+            None => panic!("no error in {:?}", input),
+        }
+    }
+}
+
+#[test]
+fn test_lines_and_columns() {
+    let err = first_error("{:a 1\n :b [2\n     3 \\foo]}");
+    assert_eq!(
+        err.start,
+        Position {
+            line: 3,
+            column: 8,
+            utf16_column: 8,
+        }
+    );
+    assert_eq!(
+        err.end,
+        Position {
+            line: 3,
+            column: 12,
+            utf16_column: 12,
+        }
+    );
+
+    // `é` is one UTF-16 code unit and `😀` is two.
+    let err = first_error("[\"é😀\" ; ü\n  \"😀\\q\"]");
+    assert_eq!(
+        err.kind,
+        ErrorKind::InvalidEscape {
+            escape: "\\q".into()
+        }
+    );
+    assert_eq!(
+        (err.start.line, err.start.column, err.start.utf16_column),
+        (2, 5, 6)
+    );
+
+    let err = first_error("(1 2\n");
+    assert_eq!((err.start.line, err.start.column), (1, 1));
+    assert_eq!((err.end.line, err.end.column), (2, 1));
+}
+
+#[test]
+fn test_display() {
+    let err = first_error("[1\n 2 }");
+    assert_eq!(err.to_string(), "2:4: unexpected `}`");
+    let err = err.with_source_name("config.edn");
+    assert_eq!(err.to_string(), "config.edn:2:4: unexpected `}`");
+
+    let mut parser = Parser::new("\n\n  #1").with_source_name("a/b.edn");
+    let err = parser.read().unwrap().unwrap_err();
+    assert_eq!(err.source_name, Some("a/b.edn".into()));
+    assert_eq!(err.to_string(), "a/b.edn:3:3: invalid dispatch `#1`");
+}
+
+#[test]
+fn test_std_error() {
+    fn read(input: &str) -> Result<edn::Value, Box<dyn error::Error>> {
+        Ok(Parser::new(input).read().unwrap()?)
+    }
+    assert!(read("1").is_ok());
+    assert_eq!(read("[").unwrap_err().to_string(), "1:1: unclosed `[`");
+}
+
+#[test]
+fn test_reader_positions() {
+    let mut input = String::new();
+    for i in 0..1_000 {
+        input.push_str(&format!("{{:id {} :name \"ü{}\"}}\n", i, i));
+    }
+    input.push_str("  \"😀\" [1 \\bad]");
+    let expected = first_error(&input).with_source_name("in.edn");
+    assert_eq!((expected.start.line, expected.start.column), (1_001, 10));
+
+    for &capacity in &[1, 7, 64, 8192] {
+        let reader = BufReader::with_capacity(capacity, input.as_bytes());
+        let mut parser = ReaderParser::new(reader).with_source_name("in.edn");
+        let err = loop {
+            match parser.read() {
+                Some(Ok(_)) => {}
+                Some(Err(err)) => break err,
+                None => panic!("no error"),
+            }
+        };
+        assert_eq!(err, expected);
+    }
+
+    let mut parser = PushParser::new();
+    assert_eq!(parser.feed(b"1\n2\n").count(), 2);
+    assert_eq!(parser.feed(b"  [3\n").count(), 0);
+    let err = parser.finish().next().unwrap().unwrap_err();
+    assert_eq!(err.to_string(), "3:3: unclosed `[`");
+}
+
+#[test]
+fn test_kinds() {
+    let cases = vec![
+        (
+            "(1",
+            ErrorKind::UnclosedDelimiter { open: "(" },
+            "unclosed `(`",
+        ),
+        (
+            "#{1",
+            ErrorKind::UnclosedDelimiter { open: "#{" },
+            "unclosed `#{`",
+        ),
+        (
+            "]",
+            ErrorKind::UnexpectedDelimiter { delimiter: ']' },
+            "unexpected `]`",
+        ),
+        ("@", ErrorKind::UnexpectedChar { ch: '@' }, "unexpected `@`"),
+        (
+            "\"a",
+            ErrorKind::UnclosedString,
+            "expected closing `\"`, found EOF",
+        ),
+        (
+            "#:",
+            ErrorKind::InvalidDispatch {
+                dispatch: "#:".into(),
+            },
+            "invalid dispatch `#:`",
+        ),
+        (
+            "{1}",
+            ErrorKind::OddMapEntries,
+            "odd number of items in a Map",
+        ),
+        (
+            "#a",
+            ErrorKind::MissingTaggedValue,
+            "malformed tagged value",
+        ),
+        (
+            "#_",
+            ErrorKind::MissingDiscardedValue,
+            "malformed discarded value",
+        ),
+        (
+            "[99999999999999999999]",
+            ErrorKind::NumberOverflow {
+                number: "99999999999999999999".into(),
+            },
+            "number `99999999999999999999` is out of range",
+        ),
+    ];
+    for (input, kind, message) in cases {
+        let err = first_error(input);
+        assert_eq!(err.kind, kind, "{}", input);
+        assert_eq!(err.kind.to_string(), message);
+    }
+
+    let err = first_error("-9223372036854775809");
+    assert_eq!((err.lo, err.hi), (0, 20));
+    assert_eq!(
+        Parser::new("-9223372036854775808").read(),
+        Some(Ok(edn::Value::Integer(i64::MIN)))
+    );
+    assert_eq!(
+        edn::cst::Document::parse("[1 99999999999999999999]").map_err(|err| err.kind),
+        Err(ErrorKind::NumberOverflow {
+            number: "99999999999999999999".into(),
+        })
+    );
+}
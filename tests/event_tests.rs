@@ -0,0 +1,185 @@
+extern crate edn;
+
+use std::borrow::Cow;
+
+use edn::event::{Event, EventKind, EventReader};
+use edn::parser::{Error, ErrorKind, Position};
+use edn::BorrowedValue;
+
+fn position(line: usize, column: usize) -> Position {
+    Position {
+        line,
+        column,
+        utf16_column: column,
+    }
+}
+
+fn kinds(input: &str) -> Vec<EventKind<'_>> {
+    EventReader::new(input)
+        .map(|event| event.unwrap().kind)
+        .collect()
+}
+
+fn symbol(name: &str) -> EventKind<'_> {
+    EventKind::Scalar(BorrowedValue::Symbol(Cow::Borrowed(name)))
+}
+
+#[test]
+fn test_events() {
+    assert_eq!(
+        kinds(r#"(defn f [x] {:a #{1}}) #inst "2017" nil"#),
+        vec![
+            EventKind::StartList,
+            symbol("defn"),
+            symbol("f"),
+            EventKind::StartVector,
+            symbol("x"),
+            EventKind::End,
+            EventKind::StartMap,
+            EventKind::Scalar(BorrowedValue::Keyword(Cow::Borrowed("a"))),
+            EventKind::StartSet,
+            EventKind::Scalar(BorrowedValue::Integer(1)),
+            EventKind::End,
+            EventKind::End,
+            EventKind::End,
+            EventKind::Tag("inst"),
+            EventKind::Scalar(BorrowedValue::String(Cow::Borrowed("2017"))),
+            EventKind::Scalar(BorrowedValue::Nil),
+        ]
+    );
+    assert_eq!(kinds(" ; nothing\n"), vec![]);
+}
+
+#[test]
+fn test_event_spans() {
+    let events = EventReader::new("[1 #_ (2 3) #{\"x\"}]")
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        events,
+        vec![
+            Event {
+                kind: EventKind::StartVector,
+                lo: 0,
+                hi: 1,
+            },
+            Event {
+                kind: EventKind::Scalar(BorrowedValue::Integer(1)),
+                lo: 1,
+                hi: 2,
+            },
+            Event {
+                kind: EventKind::Discard,
+                lo: 3,
+                hi: 11,
+            },
+            Event {
+                kind: EventKind::StartSet,
+                lo: 12,
+                hi: 14,
+            },
+            Event {
+                kind: EventKind::Scalar(BorrowedValue::String(Cow::Borrowed("x"))),
+                lo: 14,
+                hi: 17,
+            },
+            Event {
+                kind: EventKind::End,
+                lo: 17,
+                hi: 18,
+            },
+            Event {
+                kind: EventKind::End,
+                lo: 18,
+                hi: 19,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_event_depth() {
+    let mut reader = EventReader::new("[{:a (1)}] 2");
+    let mut depths = vec![];
+    while let Some(event) = reader.next() {
+        event.unwrap();
+        depths.push(reader.depth());
+    }
+    assert_eq!(depths, vec![1, 2, 2, 3, 3, 2, 1, 0, 0]);
+}
+
+#[test]
+fn test_event_errors() {
+    let error = |input| EventReader::new(input).find_map(Result::err);
+    assert_eq!(
+        error("[1 2"),
+        Some(Error {
+            lo: 0,
+            hi: 4,
+            kind: ErrorKind::UnclosedDelimiter { open: "[" },
+            start: position(1, 1),
+            end: position(1, 5),
+            source_name: None,
+        })
+    );
+    assert_eq!(
+        error("{:a 1 :b}"),
+        Some(Error {
+            lo: 0,
+            hi: 9,
+            kind: ErrorKind::OddMapEntries,
+            start: position(1, 1),
+            end: position(1, 10),
+            source_name: None,
+        })
+    );
+    assert_eq!(
+        error("(1 ]"),
+        Some(Error {
+            lo: 3,
+            hi: 4,
+            kind: ErrorKind::UnexpectedDelimiter { delimiter: ']' },
+            start: position(1, 4),
+            end: position(1, 5),
+            source_name: None,
+        })
+    );
+    assert_eq!(
+        error("[#foo]"),
+        Some(Error {
+            lo: 5,
+            hi: 6,
+            kind: ErrorKind::UnexpectedDelimiter { delimiter: ']' },
+            start: position(1, 6),
+            end: position(1, 7),
+            source_name: None,
+        })
+    );
+    assert_eq!(
+        error("#foo "),
+        Some(Error {
+            lo: 1,
+            hi: 5,
+            kind: ErrorKind::MissingTaggedValue,
+            start: position(1, 2),
+            end: position(1, 6),
+            source_name: None,
+        })
+    );
+    assert_eq!(
+        error("#_"),
+        Some(Error {
+            lo: 0,
+            hi: 2,
+            kind: ErrorKind::MissingDiscardedValue,
+            start: position(1, 1),
+            end: position(1, 3),
+            source_name: None,
+        })
+    );
+
+    // Nothing is read after an error.
+    let mut reader = EventReader::new(") 1");
+    assert!(reader.next().unwrap().is_err());
+    assert_eq!(reader.next(), None);
+}
@@ -1,11 +1,18 @@
 extern crate edn;
 extern crate ordered_float;
 
-use edn::parser::{Error, Parser};
-use edn::Value;
+use edn::{Map, Set, Value};
 use ordered_float::OrderedFloat;
 use std::collections::{BTreeMap, BTreeSet};
 
+// `Value::List` and `Value::Vector` hold an `edn::Vector`, which is only a
+// `Vec` without the `persistent` feature.
+macro_rules! vector {
+    ($($item:expr),* $(,)*) => {
+        vec![$($item),*].into_iter().collect::<edn::Vector>()
+    };
+}
+
 #[test]
 fn from_bool() {
     assert_eq!(Value::from(true), Value::Boolean(true));
@@ -46,13 +53,13 @@ fn from_num() {
 
 #[test]
 fn from_vec() {
-    assert_eq!(Value::from(Vec::<i64>::new()), Value::Vector(vec![]));
-    assert_eq!(Value::from(Vec::<Value>::new()), Value::Vector(vec![]));
-    assert_eq!(Value::from(Vec::<String>::new()), Value::Vector(vec![]));
+    assert_eq!(Value::from(Vec::<i64>::new()), Value::Vector(vector![]));
+    assert_eq!(Value::from(Vec::<Value>::new()), Value::Vector(vector![]));
+    assert_eq!(Value::from(Vec::<String>::new()), Value::Vector(vector![]));
 
     assert_eq!(
         Value::from(vec![1, 2, 3]),
-        Value::Vector(vec![
+        Value::Vector(vector![
             Value::Integer(1),
             Value::Integer(2),
             Value::Integer(3),
@@ -65,7 +72,7 @@ fn from_map() {
     let mut m = BTreeMap::new();
     m.insert(1, 2);
 
-    let mut n = BTreeMap::new();
+    let mut n = Map::new();
     n.insert(Value::Integer(1), Value::Integer(2));
     assert_eq!(Value::from(m), Value::Map(n));
 }
@@ -75,7 +82,7 @@ fn from_set() {
     m.insert(1);
     m.insert(2);
 
-    let mut n = BTreeSet::new();
+    let mut n = Set::new();
     n.insert(Value::Integer(1));
     n.insert(Value::Integer(2));
     assert_eq!(Value::from(m), Value::Set(n));
@@ -0,0 +1,93 @@
+extern crate edn;
+extern crate ordered_float;
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use edn::parser::Parser;
+use edn::{Map, Set, Value};
+use ordered_float::OrderedFloat;
+
+fn hash(value: &Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn read(str: &str) -> Value {
+    Parser::new(str).read().unwrap().unwrap()
+}
+
+#[test]
+fn test_hash_floats() {
+    let zero = Value::Float(OrderedFloat(0.0));
+    let negative_zero = Value::Float(OrderedFloat(-0.0));
+    assert_eq!(zero, negative_zero);
+    assert_eq!(hash(&zero), hash(&negative_zero));
+
+    let nan = Value::Float(OrderedFloat(f64::NAN));
+    let other_nan = Value::Float(OrderedFloat(-f64::NAN));
+    assert_eq!(nan, other_nan);
+    assert_eq!(hash(&nan), hash(&other_nan));
+
+    assert_ne!(hash(&zero), hash(&Value::Float(OrderedFloat(1.0))));
+}
+
+#[test]
+fn test_hash_distinguishes_variants() {
+    assert_ne!(
+        hash(&Value::String("foo".into())),
+        hash(&Value::Symbol("foo".into()))
+    );
+    assert_ne!(
+        hash(&Value::Symbol("foo".into())),
+        hash(&Value::Keyword("foo".into()))
+    );
+    assert_ne!(hash(&read("(1 2)")), hash(&read("[1 2]")));
+    assert_ne!(hash(&read("[]")), hash(&read("#{}")));
+}
+
+#[test]
+fn test_hash_collections() {
+    assert_eq!(hash(&read("{:a 1 :b 2}")), hash(&read("{:b 2, :a 1}")));
+    assert_eq!(hash(&read("#{1 2 3}")), hash(&read("#{3 1 2}")));
+    assert_eq!(
+        hash(&read("[{:a #{1 2}} #tag (1.5)]")),
+        hash(&read("[{:a #{2 1}} #tag (1.5)]"))
+    );
+    assert_ne!(hash(&read("[[1] 2]")), hash(&read("[1 [2]]")));
+}
+
+#[test]
+fn test_values_as_hash_keys() {
+    let mut map = HashMap::new();
+    map.insert(read("{:id 1 :tags #{:a :b}}"), "first");
+    map.insert(read("[:x -0.0]"), "second");
+
+    assert_eq!(map.get(&read("{:tags #{:b :a} :id 1}")), Some(&"first"));
+    assert_eq!(map.get(&read("[:x 0.0]")), Some(&"second"));
+    assert_eq!(map.get(&read("[:x 0]")), None);
+
+    let set = vec![read(":a"), read(":b"), read(":a")]
+        .into_iter()
+        .collect::<HashSet<_>>();
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn test_from_hash_collections() {
+    let mut m = HashMap::new();
+    m.insert("a", 1);
+
+    let mut n = Map::new();
+    n.insert(Value::String("a".into()), Value::Integer(1));
+    assert_eq!(Value::from(m), Value::Map(n));
+
+    let s = vec![1, 2, 2].into_iter().collect::<HashSet<_>>();
+
+    let mut t = Set::new();
+    t.insert(Value::Integer(1));
+    t.insert(Value::Integer(2));
+    assert_eq!(Value::from(s), Value::Set(t));
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "intern")]
+
+extern crate edn;
+
+use edn::intern::{Interned, Interner};
+use edn::parser::Parser;
+use edn::Value;
+
+fn names(value: &Value) -> Vec<*const u8> {
+    match value {
+        Value::Symbol(name) | Value::Keyword(name) => vec![name.as_ptr()],
+        Value::Vector(items) | Value::List(items) => items.iter().flat_map(names).collect(),
+        _ => vec![],
+    }
+}
+
+#[test]
+fn test_repeated_names_are_shared() {
+    let mut parser = Parser::new("[:a :b :a foo foo :foo]");
+    let ptrs = names(&parser.read().unwrap().unwrap());
+    assert_eq!(ptrs[0], ptrs[2]);
+    assert_ne!(ptrs[0], ptrs[1]);
+    assert_eq!(ptrs[3], ptrs[4]);
+    assert_eq!(parser.into_interner().len(), 3);
+}
+
+#[test]
+fn test_interner_can_be_shared_between_parsers() {
+    let mut parser = Parser::new(":shared");
+    let first = parser.read().unwrap().unwrap();
+    let mut parser = Parser::with_interner("(:shared)", parser.into_interner());
+    let second = parser.read().unwrap().unwrap();
+    assert_eq!(names(&first)[0], names(&second)[0]);
+}
+
+#[test]
+fn test_interned_names_behave_like_strings() {
+    let mut interner = Interner::new();
+    let name = interner.intern("foo/bar");
+    assert_eq!(name, Interned::from("foo/bar"));
+    assert_eq!(name, "foo/bar");
+    assert_eq!(&*name, "foo/bar");
+    assert!(name < interner.intern("foo/baz"));
+
+    let value = Parser::new(":foo/bar").read().unwrap().unwrap();
+    assert_eq!(value, Value::Keyword("foo/bar".into()));
+    assert_eq!(format!("{:?}", value), "Keyword(\"foo/bar\")");
+    assert_eq!(value.to_string(), ":foo/bar");
+}
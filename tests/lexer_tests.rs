@@ -0,0 +1,122 @@
+extern crate edn;
+
+use edn::lexer::{Lexer, Token, TokenKind};
+
+fn tokens(input: &str) -> Vec<(TokenKind, &str)> {
+    Lexer::new(input)
+        .map(|token| (token.kind, &input[token.lo..token.hi]))
+        .collect()
+}
+
+#[test]
+fn test_lex() {
+    use edn::lexer::TokenKind::*;
+
+    assert_eq!(
+        tokens("(def x [1 -2.5, \"s\\n\" \\a]) ; done\n{:k #{sym}} #inst \"t\" #_ nil"),
+        vec![
+            (LeftParen, "("),
+            (Symbol, "def"),
+            (Whitespace, " "),
+            (Symbol, "x"),
+            (Whitespace, " "),
+            (LeftBracket, "["),
+            (Integer, "1"),
+            (Whitespace, " "),
+            (Float, "-2.5"),
+            (Comma, ","),
+            (Whitespace, " "),
+            (String, "\"s\\n\""),
+            (Whitespace, " "),
+            (Char, "\\a"),
+            (RightBracket, "]"),
+            (RightParen, ")"),
+            (Whitespace, " "),
+            (Comment, "; done"),
+            (Whitespace, "\n"),
+            (LeftBrace, "{"),
+            (Keyword, ":k"),
+            (Whitespace, " "),
+            (HashBrace, "#{"),
+            (Symbol, "sym"),
+            (RightBrace, "}"),
+            (RightBrace, "}"),
+            (Whitespace, " "),
+            (Tag, "#inst"),
+            (Whitespace, " "),
+            (String, "\"t\""),
+            (Whitespace, " "),
+            (Discard, "#_"),
+            (Whitespace, " "),
+            (Symbol, "nil"),
+        ]
+    );
+}
+
+#[test]
+fn test_lex_errors() {
+    use edn::lexer::TokenKind::*;
+
+    assert_eq!(
+        tokens("@x #:k \"bad \\q\" \\foo ~ \u{a0}→ \"open"),
+        vec![
+            (Error, "@"),
+            (Symbol, "x"),
+            (Whitespace, " "),
+            (Error, "#:"),
+            (Symbol, "k"),
+            (Whitespace, " "),
+            (Error, "\"bad \\q\""),
+            (Whitespace, " "),
+            (Error, "\\foo"),
+            (Whitespace, " "),
+            (Error, "~"),
+            (Whitespace, " \u{a0}"),
+            (Error, "→"),
+            (Whitespace, " "),
+            (Error, "\"open"),
+        ]
+    );
+}
+
+#[test]
+fn test_lex_symbols_and_numbers() {
+    use edn::lexer::TokenKind::*;
+
+    assert_eq!(
+        tokens("-1a .5 : λ/x #é 1e5 2N :k/v -22/7 1/x"),
+        vec![
+            (Error, "-1a"),
+            (Whitespace, " "),
+            (Error, ".5"),
+            (Whitespace, " "),
+            (Error, ":"),
+            (Whitespace, " "),
+            (Symbol, "λ/x"),
+            (Whitespace, " "),
+            (Tag, "#é"),
+            (Whitespace, " "),
+            (Float, "1e5"),
+            (Whitespace, " "),
+            (Integer, "2N"),
+            (Whitespace, " "),
+            (Keyword, ":k/v"),
+            (Whitespace, " "),
+            (Ratio, "-22/7"),
+            (Whitespace, " "),
+            (Error, "1/x"),
+        ]
+    );
+}
+
+#[test]
+fn test_lex_spans_cover_input() {
+    let input = "{:a [1 2.0 \\space \"x\\\"y\"]}, ;; comment\r\n#{#_ (3) 'q}\u{2003}";
+    let mut pos = 0;
+    for Token { lo, hi, .. } in Lexer::new(input) {
+        assert_eq!(lo, pos);
+        assert!(hi > lo);
+        pos = hi;
+    }
+    assert_eq!(pos, input.len());
+}
@@ -1,8 +1,24 @@
 extern crate edn;
 extern crate ordered_float;
 
-use edn::parser::{Error, Parser};
-use edn::Value;
+use edn::parser::{Error, ErrorKind, Limit, Overflow, Parser, ParserOptions, Position};
+use edn::{Ratio, Value};
+
+// `Value::List` and `Value::Vector` hold an `edn::Vector`, which is only a
+// `Vec` without the `persistent` feature.
+macro_rules! vector {
+    ($($item:expr),* $(,)*) => {
+        vec![$($item),*].into_iter().collect::<edn::Vector>()
+    };
+}
+
+fn position(line: usize, column: usize) -> Position {
+    Position {
+        line,
+        column,
+        utf16_column: column,
+    }
+}
 
 #[test]
 fn test_read_empty() {
@@ -34,7 +50,7 @@ fn test_read_integers() {
 fn test_read_floats() {
     use ordered_float::OrderedFloat;
 
-    let mut parser = Parser::new("0. 0.0 -0.0 +0.0 1.23 +1.23 -1.23 .125");
+    let mut parser = Parser::new("0. 0.0 -0.0 +0.0 1.23 +1.23 -1.23 0.125");
     assert_eq!(parser.read(), Some(Ok(Value::Float(OrderedFloat(0.0)))));
     assert_eq!(parser.read(), Some(Ok(Value::Float(OrderedFloat(0.0)))));
     assert_eq!(parser.read(), Some(Ok(Value::Float(OrderedFloat(0.0)))));
@@ -63,9 +79,59 @@ fn test_read_chars() {
         Some(Err(Error {
             lo: 2,
             hi: 6,
-            message: "invalid char literal `\\foo`".into()
+            kind: ErrorKind::InvalidCharLiteral {
+                literal: "\\foo".into(),
+            },
+            start: position(1, 3),
+            end: position(1, 7),
+            source_name: None,
+        }))
+    );
+}
+
+#[test]
+fn test_read_unicode_chars() {
+    let mut parser = Parser::new("\\u00e9 \\u0041 \\u03BB \\u");
+    assert_eq!(parser.read(), Some(Ok(Value::Char('é'))));
+    assert_eq!(parser.read(), Some(Ok(Value::Char('A'))));
+    assert_eq!(parser.read(), Some(Ok(Value::Char('λ'))));
+    assert_eq!(parser.read(), Some(Ok(Value::Char('u'))));
+    assert_eq!(parser.read(), None);
+
+    // Surrogates aren't characters, and there must be exactly four digits.
+    for &input in &["\\uD800", "\\u12", "\\u12345", "\\u+0e9", "\\u00eg"] {
+        let err = Parser::new(input).read().unwrap().unwrap_err();
+        assert_eq!(
+            err.kind,
+            ErrorKind::InvalidCharLiteral {
+                literal: input.into()
+            },
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_read_backslash_before_whitespace() {
+    let mut parser = Parser::new("[\\ ]");
+    assert_eq!(
+        parser.read(),
+        Some(Err(Error {
+            lo: 1,
+            hi: 2,
+            kind: ErrorKind::InvalidCharLiteral {
+                literal: "\\".into(),
+            },
+            start: position(1, 2),
+            end: position(1, 3),
+            source_name: None,
         }))
     );
+    for &input in &["\\\n", "\\\t", "\\"] {
+        let err = Parser::new(input).read().unwrap().unwrap_err();
+        assert_eq!((err.lo, err.hi), (0, 1), "{:?}", input);
+    }
 }
 
 #[test]
@@ -91,7 +157,12 @@ quux"
         Some(Err(Error {
             lo: 4,
             hi: 6,
-            message: "invalid string escape `\\x`".into()
+            kind: ErrorKind::InvalidEscape {
+                escape: "\\x".into(),
+            },
+            start: position(1, 5),
+            end: position(1, 7),
+            source_name: None,
         }))
     );
 
@@ -101,7 +172,10 @@ quux"
         Some(Err(Error {
             lo: 3,
             hi: 7,
-            message: "expected closing `\"`, found EOF".into()
+            kind: ErrorKind::UnclosedString,
+            start: position(1, 4),
+            end: position(1, 8),
+            source_name: None,
         }))
     );
 }
@@ -189,11 +263,11 @@ fn test_read_lists() {
                                   (((\"foo\" \"bar\")))",
     );
 
-    assert_eq!(parser.read(), Some(Ok(Value::List(vec![]))));
+    assert_eq!(parser.read(), Some(Ok(Value::List(vector![]))));
 
     assert_eq!(
         parser.read(),
-        Some(Ok(Value::List(vec![
+        Some(Ok(Value::List(vector![
             Value::Integer(1),
             Value::Integer(2),
             Value::Integer(3),
@@ -202,7 +276,7 @@ fn test_read_lists() {
 
     assert_eq!(
         parser.read(),
-        Some(Ok(Value::List(vec![
+        Some(Ok(Value::List(vector![
             Value::Boolean(true),
             Value::Boolean(false),
             Value::Nil,
@@ -211,10 +285,9 @@ fn test_read_lists() {
 
     assert_eq!(
         parser.read(),
-        Some(Ok(Value::List(vec![Value::List(vec![Value::List(vec![
-            Value::String("foo".into()),
-            Value::String("bar".into()),
-        ])])])))
+        Some(Ok(Value::List(vector![Value::List(vector![Value::List(
+            vector![Value::String("foo".into()), Value::String("bar".into())]
+        )])])))
     );
 
     assert_eq!(parser.read(), None);
@@ -225,7 +298,12 @@ fn test_read_lists() {
         Some(Err(Error {
             lo: 4,
             hi: 8,
-            message: "invalid char literal `\\foo`".into()
+            kind: ErrorKind::InvalidCharLiteral {
+                literal: "\\foo".into(),
+            },
+            start: position(1, 5),
+            end: position(1, 9),
+            source_name: None,
         }))
     );
 
@@ -235,7 +313,10 @@ fn test_read_lists() {
         Some(Err(Error {
             lo: 2,
             hi: 10,
-            message: "unclosed `(`".into()
+            kind: ErrorKind::UnclosedDelimiter { open: "(" },
+            start: position(1, 3),
+            end: position(1, 11),
+            source_name: None,
         }))
     );
 }
@@ -247,11 +328,11 @@ fn test_read_vectors() {
                                   [[[\"foo\" \"bar\"]]]",
     );
 
-    assert_eq!(parser.read(), Some(Ok(Value::Vector(vec![]))));
+    assert_eq!(parser.read(), Some(Ok(Value::Vector(vector![]))));
 
     assert_eq!(
         parser.read(),
-        Some(Ok(Value::Vector(vec![
+        Some(Ok(Value::Vector(vector![
             Value::Integer(1),
             Value::Integer(2),
             Value::Integer(3),
@@ -260,7 +341,7 @@ fn test_read_vectors() {
 
     assert_eq!(
         parser.read(),
-        Some(Ok(Value::Vector(vec![
+        Some(Ok(Value::Vector(vector![
             Value::Boolean(true),
             Value::Boolean(false),
             Value::Nil,
@@ -269,9 +350,12 @@ fn test_read_vectors() {
 
     assert_eq!(
         parser.read(),
-        Some(Ok(Value::Vector(vec![Value::Vector(vec![Value::Vector(
-            vec![Value::String("foo".into()), Value::String("bar".into())],
-        )])])))
+        Some(Ok(Value::Vector(vector![Value::Vector(vector![
+            Value::Vector(vector![
+                Value::String("foo".into()),
+                Value::String("bar".into())
+            ])
+        ])])))
     );
 
     assert_eq!(parser.read(), None);
@@ -282,7 +366,12 @@ fn test_read_vectors() {
         Some(Err(Error {
             lo: 4,
             hi: 8,
-            message: "invalid char literal `\\foo`".into()
+            kind: ErrorKind::InvalidCharLiteral {
+                literal: "\\foo".into(),
+            },
+            start: position(1, 5),
+            end: position(1, 9),
+            source_name: None,
         }))
     );
 
@@ -292,26 +381,29 @@ fn test_read_vectors() {
         Some(Err(Error {
             lo: 2,
             hi: 10,
-            message: "unclosed `[`".into()
+            kind: ErrorKind::UnclosedDelimiter { open: "[" },
+            start: position(1, 3),
+            end: position(1, 11),
+            source_name: None,
         }))
     );
 }
 
 #[test]
 fn test_read_maps() {
-    use std::collections::BTreeMap;
+    use edn::Map;
 
     let mut parser = Parser::new(
         "{} {1 2} {true, false}
                                   {{\"foo\" \"bar\"} \"baz\"}",
     );
 
-    assert_eq!(parser.read(), Some(Ok(Value::Map(BTreeMap::new()))));
+    assert_eq!(parser.read(), Some(Ok(Value::Map(Map::new()))));
 
     assert_eq!(
         parser.read(),
         Some(Ok(Value::Map({
-            let mut map = BTreeMap::new();
+            let mut map = Map::new();
             map.insert(Value::Integer(1), Value::Integer(2));
             map
         })))
@@ -320,7 +412,7 @@ fn test_read_maps() {
     assert_eq!(
         parser.read(),
         Some(Ok(Value::Map({
-            let mut map = BTreeMap::new();
+            let mut map = Map::new();
             map.insert(Value::Boolean(true), Value::Boolean(false));
             map
         })))
@@ -329,10 +421,10 @@ fn test_read_maps() {
     assert_eq!(
         parser.read(),
         Some(Ok(Value::Map({
-            let mut map = BTreeMap::new();
+            let mut map = Map::new();
             map.insert(
                 Value::Map({
-                    let mut map = BTreeMap::new();
+                    let mut map = Map::new();
                     map.insert(Value::String("foo".into()), Value::String("bar".into()));
                     map
                 }),
@@ -348,7 +440,12 @@ fn test_read_maps() {
         Some(Err(Error {
             lo: 1,
             hi: 5,
-            message: "invalid char literal `\\foo`".into()
+            kind: ErrorKind::InvalidCharLiteral {
+                literal: "\\foo".into(),
+            },
+            start: position(1, 2),
+            end: position(1, 6),
+            source_name: None,
         }))
     );
 
@@ -358,7 +455,10 @@ fn test_read_maps() {
         Some(Err(Error {
             lo: 2,
             hi: 9,
-            message: "unclosed `{`".into()
+            kind: ErrorKind::UnclosedDelimiter { open: "{" },
+            start: position(1, 3),
+            end: position(1, 10),
+            source_name: None,
         }))
     );
 
@@ -368,7 +468,10 @@ fn test_read_maps() {
         Some(Err(Error {
             lo: 0,
             hi: 7,
-            message: "odd number of items in a Map".into()
+            kind: ErrorKind::OddMapEntries,
+            start: position(1, 1),
+            end: position(1, 8),
+            source_name: None,
         }))
     );
 
@@ -378,7 +481,10 @@ fn test_read_maps() {
         Some(Err(Error {
             lo: 1,
             hi: 8,
-            message: "odd number of items in a Map".into()
+            kind: ErrorKind::OddMapEntries,
+            start: position(1, 2),
+            end: position(1, 9),
+            source_name: None,
         }))
     );
 }
@@ -388,12 +494,10 @@ fn test_read_sets() {
     let mut parser = Parser::new(
         "#{} #{1 2 2 3 3 3} #{true, false, nil}
                                   #{#{#{\"foo\" \"bar\"}}}",
-    );
+    )
+    .with_options(ParserOptions::new().allow_duplicates(true));
 
-    assert_eq!(
-        parser.read(),
-        Some(Ok(Value::Set([].iter().cloned().collect())))
-    );
+    assert_eq!(parser.read(), Some(Ok(Value::Set(edn::Set::new()))));
 
     assert_eq!(
         parser.read(),
@@ -424,12 +528,14 @@ fn test_read_sets() {
                         .iter()
                         .cloned()
                         .collect()
-                )].iter()
-                    .cloned()
-                    .collect()
-            )].iter()
+                )]
+                .iter()
                 .cloned()
                 .collect()
+            )]
+            .iter()
+            .cloned()
+            .collect()
         )))
     );
 
@@ -441,7 +547,12 @@ fn test_read_sets() {
         Some(Err(Error {
             lo: 6,
             hi: 10,
-            message: "invalid char literal `\\foo`".into()
+            kind: ErrorKind::InvalidCharLiteral {
+                literal: "\\foo".into(),
+            },
+            start: position(1, 7),
+            end: position(1, 11),
+            source_name: None,
         }))
     );
 
@@ -451,9 +562,601 @@ fn test_read_sets() {
         Some(Err(Error {
             lo: 3,
             hi: 11,
-            message: "unclosed `#{`".into()
+            kind: ErrorKind::UnclosedDelimiter { open: "#{" },
+            start: position(1, 4),
+            end: position(1, 12),
+            source_name: None,
+        }))
+    );
+}
+
+#[test]
+fn test_duplicates() {
+    let mut parser = Parser::new("{:a 1 :b 2\n :a 3}");
+    assert_eq!(
+        parser.read(),
+        Some(Err(Error {
+            lo: 12,
+            hi: 14,
+            kind: ErrorKind::DuplicateKey {
+                first_lo: 1,
+                first_hi: 3,
+            },
+            start: position(2, 2),
+            end: position(2, 4),
+            source_name: None,
+        }))
+    );
+
+    let mut parser = Parser::new("#{1 [2] 3 [2]}");
+    let err = parser.read().unwrap().unwrap_err();
+    assert_eq!((err.lo, err.hi), (10, 13));
+    assert_eq!(
+        err.kind,
+        ErrorKind::DuplicateElement {
+            first_lo: 4,
+            first_hi: 7,
+        }
+    );
+    assert_eq!(err.to_string(), "1:11: duplicate set element");
+
+    // Keys are compared as values, and discarded values are checked too.
+    for &(input, lo, hi) in &[
+        ("{0 :a 0.0 :b #_ 1 0 :d}", 18, 19),
+        ("[#_ {:a 1 :a 2} 3]", 10, 12),
+        ("{[1 2] 1 [1, 2] 2}", 9, 15),
+    ] {
+        let err = Parser::new(input).read().unwrap().unwrap_err();
+        assert_eq!((err.lo, err.hi), (lo, hi), "{}", input);
+        let err = Parser::new(input).read_borrowed().unwrap().unwrap_err();
+        assert_eq!((err.lo, err.hi), (lo, hi), "{}", input);
+        let err = Parser::new(input).read_spanned().unwrap().unwrap_err();
+        assert_eq!((err.lo, err.hi), (lo, hi), "{}", input);
+    }
+
+    // Tagged items span their tag, and the error keeps the options and
+    // source name, which `1/2` and `2/4` need to be read as equal ratios.
+    let mut parser = Parser::new("#{#x 1 #x 1}");
+    let err = parser.read().unwrap().unwrap_err();
+    assert_eq!((err.lo, err.hi), (7, 11));
+    let options = ParserOptions::new().allow_clojure_numbers(true);
+    let mut parser = Parser::new("{1/2 :a\n 2/4 :b}")
+        .with_options(options)
+        .with_source_name("ratios.edn");
+    assert_eq!(
+        parser.read(),
+        Some(Err(Error {
+            lo: 9,
+            hi: 12,
+            kind: ErrorKind::DuplicateKey {
+                first_lo: 1,
+                first_hi: 4,
+            },
+            start: position(2, 2),
+            end: position(2, 5),
+            source_name: Some("ratios.edn".into()),
         }))
     );
+
+    let options = ParserOptions::new().allow_duplicates(true);
+    let mut parser = Parser::new("{:a 1 :a 2} #{1 1} [#_ {:b 1 :b 2}]").with_options(options);
+    let mut expected = edn::Map::new();
+    expected.insert(Value::Keyword("a".into()), Value::Integer(2));
+    assert_eq!(parser.read(), Some(Ok(Value::Map(expected))));
+    assert_eq!(
+        parser.read(),
+        Some(Ok(Value::Set(
+            Some(Value::Integer(1)).into_iter().collect()
+        )))
+    );
+    assert_eq!(parser.read(), Some(Ok(Value::Vector(vector![]))));
+}
+
+fn read_with(input: &str, options: ParserOptions) -> Result<Value, Error> {
+    Parser::new(input).with_options(options).read().unwrap()
+}
+
+// The span and kind of the error reading `input` with `options`.
+fn error_with(input: &str, options: ParserOptions) -> (usize, usize, ErrorKind) {
+    let err = read_with(input, options).unwrap_err();
+    (err.lo, err.hi, err.kind)
+}
+
+#[test]
+fn test_options_strict() {
+    for &input in &[
+        "a/b/c", "a/", "a//b", "foo/1a", "foo/-1", ":a/", "::a", ":a/b/c", ":/",
+    ] {
+        let symbol: String = input.into();
+        assert_eq!(
+            error_with(input, ParserOptions::strict()),
+            (
+                0,
+                input.len(),
+                ErrorKind::InvalidSymbol {
+                    symbol: symbol.clone()
+                }
+            ),
+            "{}",
+            input
+        );
+        assert_eq!(
+            error_with(input, ParserOptions::new()),
+            (0, input.len(), ErrorKind::InvalidSymbol { symbol }),
+            "{}",
+            input
+        );
+        assert!(
+            read_with(input, ParserOptions::lenient()).is_ok(),
+            "{}",
+            input
+        );
+    }
+    for &input in &["/", "a/b", "-a", ".b", "+", "a:b#c", ":a/b", ":-"] {
+        assert!(
+            read_with(input, ParserOptions::strict()).is_ok(),
+            "{}",
+            input
+        );
+    }
+
+    let input = "[#inst \"2017-01-01\" #uuid \"x\" #myapp/Person {}]";
+    assert_eq!(
+        error_with(input, ParserOptions::strict()),
+        (
+            30,
+            43,
+            ErrorKind::UnknownTag {
+                tag: "myapp/Person".into()
+            }
+        )
+    );
+    let options = ParserOptions::strict().tag("myapp/Person");
+    assert!(read_with(input, options).is_ok());
+    assert!(read_with(input, ParserOptions::new()).is_ok());
+
+    for &input in &["007", "-00", "+01.5", "00.0"] {
+        for options in [ParserOptions::strict(), ParserOptions::new()] {
+            let number = input.into();
+            assert_eq!(
+                error_with(input, options),
+                (0, input.len(), ErrorKind::LeadingZeros { number }),
+                "{}",
+                input
+            );
+        }
+        let options = ParserOptions::new().allow_leading_zeros(true);
+        assert!(read_with(input, options).is_ok(), "{}", input);
+    }
+    for &input in &["0", "-0", "0.05", "100"] {
+        assert!(
+            read_with(input, ParserOptions::strict()).is_ok(),
+            "{}",
+            input
+        );
+    }
+    let options = ParserOptions::strict().allow_leading_zeros(true);
+    assert_eq!(read_with("007", options), Ok(Value::Integer(7)));
+
+    assert!(read_with("{:a 1 :a 2}", ParserOptions::strict()).is_err());
+}
+
+#[test]
+fn test_options_lenient() {
+    let input = "{:a 1 :a 2}";
+    assert!(read_with(input, ParserOptions::new()).is_err());
+    assert_eq!(
+        read_with(input, ParserOptions::lenient())
+            .unwrap()
+            .to_string(),
+        "{:a 2}"
+    );
+
+    let value = Value::Vector(vector![
+        Value::from(f64::INFINITY),
+        Value::from(f64::NEG_INFINITY),
+        Value::from(f64::NAN),
+    ]);
+    let input = value.to_string();
+    assert_eq!(input, "[##Inf ##-Inf ##NaN]");
+    assert_eq!(read_with(&input, ParserOptions::lenient()), Ok(value));
+    assert_eq!(
+        error_with(&input, ParserOptions::new()),
+        (
+            1,
+            3,
+            ErrorKind::InvalidDispatch {
+                dispatch: "##".into()
+            }
+        )
+    );
+    assert_eq!(
+        error_with("##Foo", ParserOptions::lenient()),
+        (
+            0,
+            2,
+            ErrorKind::InvalidDispatch {
+                dispatch: "##".into()
+            }
+        )
+    );
+}
+
+#[test]
+fn test_options_invalid_symbols() {
+    let on = ParserOptions::new().allow_invalid_symbols(true);
+    let off = ParserOptions::new().allow_invalid_symbols(false);
+    assert_eq!(
+        read_with("a/b/c", on.clone()),
+        Ok(Value::Symbol("a/b/c".into()))
+    );
+    assert_eq!(read_with("::a", on), Ok(Value::Keyword(":a".into())));
+    assert_eq!(
+        error_with("a/b/c", off.clone()),
+        (
+            0,
+            5,
+            ErrorKind::InvalidSymbol {
+                symbol: "a/b/c".into()
+            }
+        )
+    );
+    assert_eq!(
+        error_with("::a", off),
+        (
+            0,
+            3,
+            ErrorKind::InvalidSymbol {
+                symbol: "::a".into()
+            }
+        )
+    );
+}
+
+#[test]
+fn test_options_unknown_tags() {
+    let input = "#myapp/Person {}";
+    let on = ParserOptions::new().allow_unknown_tags(true);
+    let off = ParserOptions::new().allow_unknown_tags(false);
+    assert_eq!(
+        read_with(input, on),
+        Ok(Value::Tagged(
+            "myapp/Person".into(),
+            Box::new(Value::Map(edn::Map::new()))
+        ))
+    );
+    assert_eq!(
+        error_with(input, off.clone()),
+        (
+            0,
+            13,
+            ErrorKind::UnknownTag {
+                tag: "myapp/Person".into()
+            }
+        )
+    );
+    // The built-in tags are always allowed.
+    assert!(read_with("#inst \"2017-01-01\"", off.clone()).is_ok());
+    assert!(read_with("#uuid \"x\"", off).is_ok());
+}
+
+#[test]
+fn test_options_symbolic_floats() {
+    let on = ParserOptions::new().allow_symbolic_floats(true);
+    let off = ParserOptions::new().allow_symbolic_floats(false);
+    assert_eq!(
+        read_with("##Inf", on.clone()),
+        Ok(Value::from(f64::INFINITY))
+    );
+    assert_eq!(
+        read_with("##-Inf", on.clone()),
+        Ok(Value::from(f64::NEG_INFINITY))
+    );
+    assert_eq!(read_with("##NaN", on), Ok(Value::from(f64::NAN)));
+    for &input in &["##Inf", "##-Inf", "##NaN"] {
+        assert_eq!(
+            error_with(input, off.clone()),
+            (
+                0,
+                2,
+                ErrorKind::InvalidDispatch {
+                    dispatch: "##".into()
+                }
+            ),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_options_syntax() {
+    let cases = vec![
+        (
+            "[1 ; one\n 2]",
+            ParserOptions::new().allow_comments(false),
+            (3, 8, "comments"),
+        ),
+        (
+            "[1, 2]",
+            ParserOptions::new().allow_commas(false),
+            (2, 3, "commas"),
+        ),
+        (
+            "[1 #_ 2]",
+            ParserOptions::new().allow_discard(false),
+            (3, 5, "discarded values"),
+        ),
+        (
+            "#inst \"2017-01-01\"",
+            ParserOptions::new().allow_tagged(false),
+            (0, 5, "tagged values"),
+        ),
+    ];
+    for (input, options, (lo, hi, syntax)) in cases {
+        assert_eq!(
+            error_with(input, options.clone()),
+            (lo, hi, ErrorKind::NotAllowed { syntax }),
+            "{}",
+            input
+        );
+        assert!(read_with(input, ParserOptions::new()).is_ok());
+        assert_eq!(
+            read_with("[1 2]", options),
+            read_with("[1 2]", ParserOptions::new())
+        );
+    }
+
+    // Comments and commas at the top level, around values, are checked too.
+    let options = ParserOptions::new().allow_comments(false);
+    let mut parser = Parser::new("1 ;c").with_options(options);
+    assert_eq!(parser.read(), Some(Ok(Value::Integer(1))));
+    assert!(parser.read().unwrap().is_err());
+}
+
+#[test]
+fn test_options_overflow() {
+    let options = ParserOptions::new().on_overflow(Overflow::Float);
+    assert_eq!(
+        read_with("[99999999999999999999 -9223372036854775809 1]", options),
+        Ok(Value::Vector(vector![
+            Value::from(1e20),
+            Value::from(-9223372036854775809.0),
+            Value::Integer(1),
+        ]))
+    );
+    assert_eq!(
+        error_with("99999999999999999999", ParserOptions::new()),
+        (
+            0,
+            20,
+            ErrorKind::NumberOverflow {
+                number: "99999999999999999999".into()
+            }
+        )
+    );
+}
+
+#[test]
+fn test_options_clojure_numbers() {
+    let options = ParserOptions::new().allow_clojure_numbers(true);
+    let cases = vec![
+        ("0xFF", 255),
+        ("-0x10", -16),
+        ("0XffN", 255),
+        ("017", 15),
+        ("-017", -15),
+        ("00", 0),
+        ("2r1010", 10),
+        ("36rZZ", 1295),
+        ("+8R17", 15),
+        ("36rN", 23),
+        ("0", 0),
+        ("0N", 0),
+        ("10", 10),
+        ("-0x8000000000000000", i64::MIN),
+    ];
+    for (input, i) in cases {
+        assert_eq!(
+            read_with(input, options.clone()),
+            Ok(Value::Integer(i)),
+            "{}",
+            input
+        );
+    }
+    for &input in &["08", "0x", "0xG", "2r102", "37r1", "1r0", "0r0", "100r1"] {
+        let number = input.into();
+        assert_eq!(
+            error_with(input, options.clone()),
+            (0, input.len(), ErrorKind::InvalidNumber { number }),
+            "{}",
+            input
+        );
+    }
+    assert_eq!(
+        error_with("[0x8000000000000000]", options.clone()),
+        (
+            1,
+            19,
+            ErrorKind::NumberOverflow {
+                number: "0x8000000000000000".into()
+            }
+        )
+    );
+    let options = options.on_overflow(Overflow::Float);
+    assert_eq!(
+        read_with("[0x10000000000000000 -36r3W5E11264SGSG]", options),
+        Ok(Value::Vector(vector![
+            Value::from(2f64.powi(64)),
+            Value::from(-2f64.powi(64))
+        ]))
+    );
+
+    for &input in &["0xFF", "2r1010"] {
+        let number = input.into();
+        assert_eq!(
+            error_with(input, ParserOptions::new()),
+            (0, input.len(), ErrorKind::InvalidNumber { number }),
+            "{}",
+            input
+        );
+    }
+    assert_eq!(
+        read_with("0xFF", ParserOptions::lenient()),
+        Ok(Value::Integer(255))
+    );
+}
+
+#[test]
+fn test_options_clojure_ratios() {
+    let options = ParserOptions::new().allow_clojure_numbers(true);
+    let ratio = |numer, denom| Value::Ratio(Ratio::new(numer, denom).unwrap());
+    let cases = vec![
+        ("22/7", ratio(22, 7)),
+        ("-1/3", ratio(-1, 3)),
+        ("+2/6", ratio(1, 3)),
+        ("0/5", Value::Integer(0)),
+        ("4/2", Value::Integer(2)),
+        ("-9223372036854775808/1", Value::Integer(i64::MIN)),
+    ];
+    for (input, value) in cases {
+        assert_eq!(read_with(input, options.clone()), Ok(value), "{}", input);
+    }
+    assert_eq!(
+        read_with("[1/2 3]", options.clone()),
+        Ok(Value::Vector(vector![ratio(1, 2), Value::Integer(3)]))
+    );
+    for &input in &["1/0", "1/2/3", "1/-2", "1/2N", "1/2.0"] {
+        let number = input.into();
+        assert_eq!(
+            error_with(input, options.clone()),
+            (0, input.len(), ErrorKind::InvalidNumber { number }),
+            "{}",
+            input
+        );
+    }
+    for &input in &["9223372036854775808/3", "1/9223372036854775808"] {
+        let number = input.into();
+        assert_eq!(
+            error_with(input, options.clone()),
+            (0, input.len(), ErrorKind::NumberOverflow { number }),
+            "{}",
+            input
+        );
+    }
+    assert_eq!(
+        read_with(
+            "1/9223372036854775808",
+            options.on_overflow(Overflow::Float)
+        ),
+        Ok(Value::from(2f64.powi(-63)))
+    );
+
+    assert_eq!(
+        error_with("22/7", ParserOptions::new()),
+        (
+            0,
+            4,
+            ErrorKind::InvalidNumber {
+                number: "22/7".into()
+            }
+        )
+    );
+    assert_eq!(
+        read_with("22/7", ParserOptions::lenient()),
+        Ok(ratio(22, 7))
+    );
+}
+
+fn exceeds(limit: Limit, max: usize) -> ErrorKind {
+    ErrorKind::LimitExceeded { limit, max }
+}
+
+#[test]
+fn test_limits() {
+    let deep = "[".repeat(100_000);
+    let err = Parser::new(&deep).read().unwrap().unwrap_err();
+    assert_eq!(
+        (err.lo, err.hi, err.kind.clone()),
+        (1024, 1025, exceeds(Limit::Depth, 1024))
+    );
+    assert_eq!(
+        err.kind.to_string(),
+        "nesting depth exceeds the limit of 1024"
+    );
+    let nested = format!("{}{}", "[".repeat(2_000), "]".repeat(2_000));
+    assert!(Parser::new(&nested).read().unwrap().is_err());
+    assert!(read_with(&nested, ParserOptions::new().max_depth(2_000)).is_ok());
+    let options = ParserOptions::new().max_depth(2);
+    assert!(read_with("[[1] #a 2]", options.clone()).is_ok());
+    assert_eq!(
+        error_with("[#a #b 1]", options),
+        (4, 6, exceeds(Limit::Depth, 2))
+    );
+
+    let options = ParserOptions::new().max_values(3);
+    assert!(read_with("[1 2]", options.clone()).is_ok());
+    assert_eq!(
+        error_with("[1 2 3]", options.clone()),
+        (5, 6, exceeds(Limit::Values, 3))
+    );
+    assert_eq!(
+        error_with("#_ [1 2] 3", options.clone()),
+        (9, 10, exceeds(Limit::Values, 3))
+    );
+    let mut parser = Parser::new("[1 2] [3 4]").with_options(options);
+    assert!(parser.read().unwrap().is_ok());
+    assert!(parser.read().unwrap().is_ok());
+
+    let options = ParserOptions::new().max_string_length(3);
+    for &input in &["\"abc\"", "abc", ":abc", "#abc 1"] {
+        assert!(read_with(input, options.clone()).is_ok(), "{}", input);
+    }
+    for &input in &["\"abcd\"", "\"a\\nb\"", "abcd", ":abcd", "#abcd 1"] {
+        assert_eq!(
+            error_with(input, options.clone()),
+            (
+                0,
+                input.split(' ').next().unwrap().len(),
+                exceeds(Limit::StringLength, 3)
+            ),
+            "{}",
+            input
+        );
+    }
+
+    let options = ParserOptions::new().max_collection_length(2);
+    for &input in &[
+        "[1 2]",
+        "(1 2)",
+        "{:a 1 :b 2}",
+        "#{1 2}",
+        "[1 2 #_ 3]",
+        "[[1 2] [3 4]]",
+    ] {
+        assert!(read_with(input, options.clone()).is_ok(), "{}", input);
+    }
+    assert_eq!(
+        error_with("[1 2 3]", options.clone()),
+        (5, 6, exceeds(Limit::CollectionLength, 2))
+    );
+    assert_eq!(
+        error_with("{:a 1 :b 2 :c 3}", options.clone()),
+        (11, 13, exceeds(Limit::CollectionLength, 2))
+    );
+    assert_eq!(
+        error_with("#{1 2 #a 3}", options),
+        (6, 8, exceeds(Limit::CollectionLength, 2))
+    );
+
+    let mut parser = Parser::new("[1 2] 3").with_options(ParserOptions::new().max_input_size(4));
+    let err = parser.read().unwrap().unwrap_err();
+    assert_eq!(
+        (err.lo, err.hi, err.kind),
+        (4, 7, exceeds(Limit::InputSize, 4))
+    );
+    assert_eq!(parser.read(), None);
+    assert!(read_with("[1 2]", ParserOptions::new().max_input_size(5)).is_ok());
 }
 
 #[test]
@@ -470,7 +1173,7 @@ fn test_tagged_values() {
         parser.read(),
         Some(Ok(Value::Tagged(
             "color".into(),
-            Box::new(Value::List(vec![
+            Box::new(Value::List(vector![
                 Value::Integer(255),
                 Value::Integer(31),
                 Value::Integer(191),
@@ -499,7 +1202,10 @@ fn test_tagged_values() {
         Some(Err(Error {
             lo: 60,
             hi: 68,
-            message: "malformed tagged value".into(),
+            kind: ErrorKind::MissingTaggedValue,
+            start: position(5, 2),
+            end: position(6, 1),
+            source_name: None,
         }))
     );
 
@@ -508,7 +1214,7 @@ fn test_tagged_values() {
 
 #[test]
 fn test_comments() {
-    use std::collections::BTreeMap;
+    use edn::Map;
 
     let mut parser = Parser::new(
         "
@@ -526,7 +1232,263 @@ fn test_comments() {
     assert_eq!(parser.read(), Some(Ok(Value::Integer(0))));
     assert_eq!(parser.read(), Some(Ok(Value::Symbol("--".into()))));
     assert_eq!(parser.read(), Some(Ok(Value::Integer(0))));
-    assert_eq!(parser.read(), Some(Ok(Value::Vector(Vec::new()))));
-    assert_eq!(parser.read(), Some(Ok(Value::Map(BTreeMap::new()))));
+    assert_eq!(parser.read(), Some(Ok(Value::Vector(vector![]))));
+    assert_eq!(parser.read(), Some(Ok(Value::Map(Map::new()))));
+    assert_eq!(parser.read(), None);
+}
+
+#[test]
+fn test_discard() {
+    use edn::Map;
+
+    let mut parser =
+        Parser::new("#_ 1 2 [#_#_ 3 4 5 #_(6)] {#_ :a :b #_{} 7} #foo #_ bar baz #_ :gone #_");
+    assert_eq!(parser.read(), Some(Ok(Value::Integer(2))));
+    assert_eq!(
+        parser.read(),
+        Some(Ok(Value::Vector(vector![Value::Integer(5)])))
+    );
+    let mut map = Map::new();
+    map.insert(Value::Keyword("b".into()), Value::Integer(7));
+    assert_eq!(parser.read(), Some(Ok(Value::Map(map))));
+    assert_eq!(
+        parser.read(),
+        Some(Ok(Value::Tagged(
+            "foo".into(),
+            Box::new(Value::Symbol("baz".into()))
+        )))
+    );
+    assert_eq!(
+        parser.read(),
+        Some(Err(Error {
+            lo: 69,
+            hi: 71,
+            kind: ErrorKind::MissingDiscardedValue,
+            start: position(1, 70),
+            end: position(1, 72),
+            source_name: None,
+        }))
+    );
+
+    let mut parser = Parser::new("[1 #_]");
+    assert_eq!(
+        parser.read(),
+        Some(Err(Error {
+            lo: 5,
+            hi: 6,
+            kind: ErrorKind::UnexpectedDelimiter { delimiter: ']' },
+            start: position(1, 6),
+            end: position(1, 7),
+            source_name: None,
+        }))
+    );
+}
+
+#[test]
+fn test_read_chars_before_delimiters() {
+    let mut parser = Parser::new("[\\a \\b] (\\newline) {\\x \\(} \\space,\\;");
+    assert_eq!(
+        parser.read(),
+        Some(Ok(Value::Vector(vector![
+            Value::Char('a'),
+            Value::Char('b')
+        ])))
+    );
+    assert_eq!(
+        parser.read(),
+        Some(Ok(Value::List(vector![Value::Char('\n')])))
+    );
+    assert_eq!(
+        parser.read(),
+        Some(Ok(Value::Map({
+            let mut map = edn::Map::new();
+            map.insert(Value::Char('x'), Value::Char('('));
+            map
+        })))
+    );
+    assert_eq!(parser.read(), Some(Ok(Value::Char(' '))));
+    assert_eq!(parser.read(), Some(Ok(Value::Char(';'))));
     assert_eq!(parser.read(), None);
 }
+
+#[test]
+fn test_read_single_char_tags() {
+    let mut parser = Parser::new("#a 1 #b #c [2]");
+    assert_eq!(
+        parser.read(),
+        Some(Ok(Value::Tagged("a".into(), Box::new(Value::Integer(1)))))
+    );
+    assert_eq!(
+        parser.read(),
+        Some(Ok(Value::Tagged(
+            "b".into(),
+            Box::new(Value::Tagged(
+                "c".into(),
+                Box::new(Value::Vector(vector![Value::Integer(2)]))
+            ))
+        )))
+    );
+    assert_eq!(parser.read(), None);
+}
+
+#[test]
+fn test_read_signs_before_delimiters() {
+    let mut parser = Parser::new("(-) [+]");
+    assert_eq!(
+        parser.read(),
+        Some(Ok(Value::List(vector![Value::Symbol("-".into())])))
+    );
+    assert_eq!(
+        parser.read(),
+        Some(Ok(Value::Vector(vector![Value::Symbol("+".into())])))
+    );
+    assert_eq!(parser.read(), None);
+}
+
+#[test]
+fn test_symbol_and_number_grammar() {
+    use ordered_float::OrderedFloat;
+
+    fn symbol(name: &str) -> Result<Value, ErrorKind> {
+        Ok(Value::Symbol(name.into()))
+    }
+    fn keyword(name: &str) -> Result<Value, ErrorKind> {
+        Ok(Value::Keyword(name.into()))
+    }
+    fn float(f: f64) -> Result<Value, ErrorKind> {
+        Ok(Value::Float(OrderedFloat(f)))
+    }
+    fn invalid_symbol(symbol: &str) -> Result<Value, ErrorKind> {
+        Err(ErrorKind::InvalidSymbol {
+            symbol: symbol.into(),
+        })
+    }
+    fn invalid_number(number: &str) -> Result<Value, ErrorKind> {
+        Err(ErrorKind::InvalidNumber {
+            number: number.into(),
+        })
+    }
+
+    let cases = vec![
+        ("a", symbol("a")),
+        ("a/b", symbol("a/b")),
+        ("/", symbol("/")),
+        ("-", symbol("-")),
+        ("+a", symbol("+a")),
+        ("-.5", symbol("-.5")),
+        ("..", symbol("..")),
+        ("a1:b#c", symbol("a1:b#c")),
+        ("*x?!$%&=<>_", symbol("*x?!$%&=<>_")),
+        ("λ", symbol("λ")),
+        ("größe/名前", symbol("größe/名前")),
+        ("x٣", symbol("x٣")),
+        ("a/b/c", invalid_symbol("a/b/c")),
+        ("a/", invalid_symbol("a/")),
+        ("/a", invalid_symbol("/a")),
+        ("٣x", Err(ErrorKind::UnexpectedChar { ch: '٣' })),
+        ("→", Err(ErrorKind::UnexpectedChar { ch: '→' })),
+        (":a", keyword("a")),
+        (":a/b", keyword("a/b")),
+        (":-", keyword("-")),
+        (":é", keyword("é")),
+        (":", invalid_symbol(":")),
+        ("::foo", invalid_symbol("::foo")),
+        (":1", invalid_symbol(":1")),
+        (":a/", invalid_symbol(":a/")),
+        ("1", Ok(Value::Integer(1))),
+        ("-1", Ok(Value::Integer(-1))),
+        ("+1N", Ok(Value::Integer(1))),
+        ("1.", float(1.0)),
+        ("1.5M", float(1.5)),
+        ("2M", float(2.0)),
+        ("1e3", float(1000.0)),
+        ("-1.5E-3", float(-0.0015)),
+        ("2e+2", float(200.0)),
+        ("1a", invalid_number("1a")),
+        ("-1a", invalid_number("-1a")),
+        ("+1x", invalid_number("+1x")),
+        (".5", invalid_number(".5")),
+        ("1e", invalid_number("1e")),
+        ("1.5N", invalid_number("1.5N")),
+        ("1/2", invalid_number("1/2")),
+        ("1λ", invalid_number("1λ")),
+    ];
+    for (input, expected) in cases {
+        let mut parser = Parser::new(input);
+        let value = parser.read().unwrap().map_err(|err| err.kind);
+        assert_eq!(value, expected, "{}", input);
+        if expected.is_ok() {
+            assert_eq!(parser.read(), None, "{}", input);
+        }
+    }
+
+    assert_eq!(
+        Parser::new("#λ/ß 1").read(),
+        Some(Ok(Value::Tagged("λ/ß".into(), Box::new(Value::Integer(1)))))
+    );
+}
+
+#[test]
+fn test_read_unicode_whitespace() {
+    let mut parser = Parser::new("\u{a0}1\u{2003}\u{3000}2");
+    assert_eq!(parser.read(), Some(Ok(Value::Integer(1))));
+    assert_eq!(parser.read(), Some(Ok(Value::Integer(2))));
+    assert_eq!(parser.read(), None);
+}
+
+#[test]
+fn test_read_unexpected_characters() {
+    let mut parser = Parser::new("[1 2) ");
+    assert_eq!(
+        parser.read(),
+        Some(Err(Error {
+            lo: 4,
+            hi: 5,
+            kind: ErrorKind::UnexpectedDelimiter { delimiter: ')' },
+            start: position(1, 5),
+            end: position(1, 6),
+            source_name: None,
+        }))
+    );
+
+    let mut parser = Parser::new(" →");
+    assert_eq!(
+        parser.read(),
+        Some(Err(Error {
+            lo: 1,
+            hi: 4,
+            kind: ErrorKind::UnexpectedChar { ch: '→' },
+            start: position(1, 2),
+            end: position(1, 3),
+            source_name: None,
+        }))
+    );
+
+    let mut parser = Parser::new("#:foo{}");
+    assert_eq!(
+        parser.read(),
+        Some(Err(Error {
+            lo: 0,
+            hi: 2,
+            kind: ErrorKind::InvalidDispatch {
+                dispatch: "#:".into(),
+            },
+            start: position(1, 1),
+            end: position(1, 3),
+            source_name: None,
+        }))
+    );
+
+    let mut parser = Parser::new("\"foo\\");
+    assert_eq!(
+        parser.read(),
+        Some(Err(Error {
+            lo: 0,
+            hi: 5,
+            kind: ErrorKind::UnclosedString,
+            start: position(1, 1),
+            end: position(1, 6),
+            source_name: None,
+        }))
+    );
+}
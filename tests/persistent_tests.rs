@@ -0,0 +1,45 @@
+#![cfg(feature = "persistent")]
+
+extern crate edn;
+
+use edn::parser::Parser;
+use edn::Value;
+
+fn read(str: &str) -> Value {
+    Parser::new(str).read().unwrap().unwrap()
+}
+
+#[test]
+fn test_clones_share_structure() {
+    let config = read("{:servers [{:host \"a\"} {:host \"b\"}] :retries 3}");
+    let mut copy = config.clone();
+    if let Value::Map(ref mut map) = copy {
+        map.insert(Value::Keyword("retries".into()), Value::Integer(5));
+    }
+    assert_eq!(
+        config,
+        read("{:servers [{:host \"a\"} {:host \"b\"}] :retries 3}")
+    );
+    assert_eq!(
+        copy,
+        read("{:servers [{:host \"a\"} {:host \"b\"}] :retries 5}")
+    );
+}
+
+#[test]
+fn test_persistent_updates() {
+    let items = match read("[1 2 3]") {
+        Value::Vector(items) => items,
+        _ => panic!("expected a vector"),
+    };
+    let updated = items.update(1, Value::Integer(20));
+    assert_eq!(Value::Vector(items), read("[1 2 3]"));
+    assert_eq!(Value::Vector(updated), read("[1 20 3]"));
+
+    let set = match read("#{1 2}") {
+        Value::Set(set) => set,
+        _ => panic!("expected a set"),
+    };
+    assert_eq!(Value::Set(set.update(Value::Integer(3))), read("#{1 2 3}"));
+    assert_eq!(Value::Set(set), read("#{2 1}"));
+}
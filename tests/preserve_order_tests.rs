@@ -0,0 +1,67 @@
+#![cfg(feature = "preserve_order")]
+
+extern crate edn;
+
+use std::collections::BTreeSet;
+
+use edn::parser::{Parser, ParserOptions};
+use edn::Value;
+
+fn read(str: &str) -> Value {
+    Parser::new(str).read().unwrap().unwrap()
+}
+
+fn read_allowing_duplicates(str: &str) -> Value {
+    Parser::new(str)
+        .with_options(ParserOptions::lenient())
+        .read()
+        .unwrap()
+        .unwrap()
+}
+
+#[test]
+fn test_maps_keep_source_order() {
+    let value = read("{:zeta 1 :alpha 2 :mu {:b 3 :a 4}}");
+    assert_eq!(value.to_string(), "{:zeta 1, :alpha 2, :mu {:b 3, :a 4}}");
+    match value {
+        Value::Map(map) => assert_eq!(
+            map.keys().map(|k| k.to_string()).collect::<Vec<_>>(),
+            vec![":zeta", ":alpha", ":mu"]
+        ),
+        _ => panic!("expected a map"),
+    }
+}
+
+#[test]
+fn test_sets_keep_source_order() {
+    assert_eq!(
+        read_allowing_duplicates("#{3 1 2 1}").to_string(),
+        "#{3 1 2}"
+    );
+}
+
+#[test]
+fn test_equality_ignores_order() {
+    assert_eq!(read("{:a 1 :b 2}"), read("{:b 2 :a 1}"));
+    assert_eq!(read("#{1 2 3}"), read("#{3 2 1}"));
+    assert_ne!(read("{:a 1 :b 2}"), read("{:b 1 :a 2}"));
+}
+
+#[test]
+fn test_ordering_agrees_with_equality() {
+    let a = read("{:a 1 :b 2}");
+    let b = read("{:b 2 :a 1}");
+    assert_eq!(a.cmp(&b), ::std::cmp::Ordering::Equal);
+
+    // Values still work as keys of ordered collections.
+    let set = vec![a, b, read("{:a 2}")]
+        .into_iter()
+        .collect::<BTreeSet<_>>();
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn test_nested_map_keys() {
+    let value = read_allowing_duplicates("{{:b 1 :a 2} :x {:a 2 :b 1} :y}");
+    assert_eq!(value.to_string(), "{{:b 1, :a 2} :y}");
+}
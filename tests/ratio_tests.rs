@@ -0,0 +1,39 @@
+extern crate edn;
+
+use std::collections::BTreeSet;
+
+use edn::{Ratio, Value};
+
+#[test]
+fn test_ratio_new() {
+    let ratio = Ratio::new(-4, -6).unwrap();
+    assert_eq!((ratio.numer(), ratio.denom()), (2, 3));
+    assert_eq!(Ratio::new(3, -9), Ratio::new(-1, 3));
+    assert_eq!(Ratio::new(0, -7), Ratio::new(0, 1));
+    assert_eq!(Ratio::new(i64::MIN, i64::MIN), Ratio::new(1, 1));
+    assert_eq!(Ratio::new(1, 0), None);
+    assert_eq!(Ratio::new(i64::MIN, -1), None);
+    assert_eq!(Ratio::new(1, i64::MIN), None);
+}
+
+#[test]
+fn test_ratio_to_f64() {
+    assert_eq!(Ratio::new(1, 2).unwrap().to_f64(), 0.5);
+    assert_eq!(f64::from(Ratio::new(-22, 7).unwrap()), -22.0 / 7.0);
+}
+
+#[test]
+fn test_ratio_ordering() {
+    let ratios: BTreeSet<Ratio> = [(1, 2), (-1, 3), (2, 3), (1, 3), (i64::MAX, 2)]
+        .iter()
+        .map(|&(numer, denom)| Ratio::new(numer, denom).unwrap())
+        .collect();
+    let printed: Vec<String> = ratios.iter().map(Ratio::to_string).collect();
+    assert_eq!(
+        printed,
+        ["-1/3", "1/3", "1/2", "2/3", "9223372036854775807/2"]
+    );
+
+    let value = Value::from(Ratio::new(1, 2).unwrap());
+    assert!(Value::from(0.5) < value && value < Value::from(vec![1i64]));
+}
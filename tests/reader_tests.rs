@@ -0,0 +1,344 @@
+extern crate edn;
+
+use std::io::{self, BufRead, BufReader, Read};
+
+use edn::parser::{Error, ErrorKind, Limit, Parser, ParserOptions, Position};
+use edn::reader::{PushParser, ReaderParser};
+use edn::Value;
+
+fn position(line: usize, column: usize) -> Position {
+    Position {
+        line,
+        column,
+        utf16_column: column,
+    }
+}
+
+const INPUT: &str = r#"
+; A comment that spans several reads.
+{:name "Ünïcödé ✓" :tags #{:a :b} :chars [\é \newline \λ]}
+12345 -1.5 foo/bar #inst "2017-01-01" (nested [1 (2 {3 4})]) "esc\"aped"
+nil true false"#;
+
+fn read_all<R: BufRead>(mut parser: ReaderParser<R>) -> Vec<Result<Value, Error>> {
+    let mut values = vec![];
+    while let Some(value) = parser.read() {
+        values.push(value);
+    }
+    values
+}
+
+fn read_str(input: &str) -> Vec<Result<Value, Error>> {
+    let mut parser = Parser::new(input);
+    let mut values = vec![];
+    while let Some(value) = parser.read() {
+        values.push(value);
+    }
+    values
+}
+
+#[test]
+fn test_read_matches_parser() {
+    let expected = read_str(INPUT);
+    assert_eq!(expected.len(), 10);
+    for &capacity in &[1, 2, 3, 7, 64, 8192] {
+        let reader = BufReader::with_capacity(capacity, INPUT.as_bytes());
+        assert_eq!(read_all(ReaderParser::new(reader)), expected);
+    }
+}
+
+#[test]
+fn test_read_large_values() {
+    let mut input = String::from("[");
+    for i in 0..50_000 {
+        input.push_str(&format!("{} \"ü{}\" ", i, i));
+    }
+    input.push_str("] :end");
+    let expected = read_str(&input);
+    let reader = BufReader::with_capacity(100, input.as_bytes());
+    assert_eq!(read_all(ReaderParser::new(reader)), expected);
+}
+
+#[test]
+fn test_read_errors_with_absolute_offsets() {
+    let mut input = String::new();
+    for i in 0..10_000 {
+        input.push_str(&format!("{{:id {}}}\n", i));
+    }
+    let lo = input.len();
+    input.push_str("\"bad \\x escape\" :after");
+
+    let mut parser = ReaderParser::new(BufReader::with_capacity(16, input.as_bytes()));
+    for _ in 0..10_000 {
+        assert!(parser.read().unwrap().is_ok());
+    }
+    assert_eq!(
+        parser.read(),
+        Some(Err(Error {
+            lo: lo + 5,
+            hi: lo + 7,
+            kind: ErrorKind::InvalidEscape {
+                escape: "\\x".into(),
+            },
+            start: position(10_001, 6),
+            end: position(10_001, 8),
+            source_name: None,
+        }))
+    );
+}
+
+#[test]
+fn test_read_duplicates() {
+    let input = "[1 2]\n{:a 1\n :a 2} #{3 3}";
+    let expected = read_str(input);
+    match expected[1] {
+        Err(ref err) => assert_eq!(
+            err.kind,
+            ErrorKind::DuplicateKey {
+                first_lo: 7,
+                first_hi: 9,
+            }
+        ),
+        ref otherwise => panic!("unexpected {:?}", otherwise),
+    }
+    for &capacity in &[1, 4, 64] {
+        let reader = BufReader::with_capacity(capacity, input.as_bytes());
+        assert_eq!(read_all(ReaderParser::new(reader)), expected);
+    }
+
+    let reader = BufReader::with_capacity(4, input.as_bytes());
+    let values = read_all(ReaderParser::new(reader).with_options(ParserOptions::lenient()));
+    assert!(values.iter().all(Result::is_ok));
+    let mut parser = PushParser::new().with_options(ParserOptions::lenient());
+    assert_eq!(
+        parser.feed(input.as_bytes()).filter(Result::is_ok).count(),
+        3
+    );
+}
+
+#[test]
+fn test_read_input_size_limit() {
+    let limit = ErrorKind::LimitExceeded {
+        limit: Limit::InputSize,
+        max: 6,
+    };
+    let options = ParserOptions::new().max_input_size(6);
+    let mut parser = PushParser::new().with_options(options.clone());
+    assert_eq!(parser.feed(b"1 2 [3").count(), 2);
+    let values: Vec<_> = parser.feed(b" 4] 5").collect();
+    match values[..] {
+        [Err(ref err)] => assert_eq!((err.lo, err.hi, &err.kind), (6, 6, &limit)),
+        ref otherwise => panic!("unexpected {:?}", otherwise),
+    }
+    assert_eq!(parser.feed(b"6").count(), 0);
+    assert_eq!(parser.finish().count(), 0);
+
+    let reader = BufReader::with_capacity(1, &b"1 2 [3 4] 5"[..]);
+    let values = read_all(ReaderParser::new(reader).with_options(options));
+    assert_eq!(values.len(), 3);
+    assert_eq!(values[2].as_ref().map_err(|err| &err.kind), Err(&limit));
+}
+
+#[test]
+fn test_read_unclosed_at_eof() {
+    let input = "[1 2] (3 4";
+    let mut parser = ReaderParser::new(BufReader::with_capacity(1, input.as_bytes()));
+    assert!(parser.read().unwrap().is_ok());
+    assert_eq!(
+        parser.read(),
+        Some(Err(Error {
+            lo: 6,
+            hi: 10,
+            kind: ErrorKind::UnclosedDelimiter { open: "(" },
+            start: position(1, 7),
+            end: position(1, 11),
+            source_name: None,
+        }))
+    );
+    assert_eq!(parser.read(), None);
+}
+
+#[test]
+fn test_read_invalid_utf8() {
+    let input: &[u8] = b":ok [1 \xff 2]";
+    let mut parser = ReaderParser::new(BufReader::with_capacity(2, input));
+    assert_eq!(parser.read(), Some(Ok(Value::Keyword("ok".into()))));
+    assert_eq!(
+        parser.read(),
+        Some(Err(Error {
+            lo: 7,
+            hi: 8,
+            kind: ErrorKind::InvalidUtf8,
+            start: position(1, 8),
+            end: position(1, 9),
+            source_name: None,
+        }))
+    );
+    assert_eq!(parser.read(), None);
+
+    // A character cut off by the end of the input.
+    let input: &[u8] = b"\"\xc3";
+    let mut parser = ReaderParser::new(input);
+    assert_eq!(
+        parser.read(),
+        Some(Err(Error {
+            lo: 1,
+            hi: 2,
+            kind: ErrorKind::InvalidUtf8,
+            start: position(1, 2),
+            end: position(1, 3),
+            source_name: None,
+        }))
+    );
+    assert_eq!(parser.read(), None);
+}
+
+struct Failing<'a>(&'a [u8]);
+
+impl<'a> Read for Failing<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.0.is_empty() {
+            return Err(io::Error::other("disk on fire"));
+        }
+        self.0.read(buf)
+    }
+}
+
+#[test]
+fn test_read_io_errors() {
+    let reader = BufReader::with_capacity(4, Failing(b"1 2 [3"));
+    let mut parser = ReaderParser::new(reader);
+    assert_eq!(parser.read(), Some(Ok(Value::Integer(1))));
+    assert_eq!(parser.read(), Some(Ok(Value::Integer(2))));
+    assert_eq!(
+        parser.read(),
+        Some(Err(Error {
+            lo: 6,
+            hi: 6,
+            kind: ErrorKind::Io {
+                message: "disk on fire".into(),
+            },
+            start: position(1, 7),
+            end: position(1, 7),
+            source_name: None,
+        }))
+    );
+    assert_eq!(parser.read(), None);
+}
+
+#[test]
+fn test_push_matches_parser() {
+    let expected = read_str(INPUT);
+    for &size in &[1, 2, 5, 64] {
+        let mut parser = PushParser::new();
+        let mut values = vec![];
+        for chunk in INPUT.as_bytes().chunks(size) {
+            values.extend(parser.feed(chunk));
+        }
+        values.extend(parser.finish());
+        assert_eq!(values, expected);
+    }
+}
+
+#[test]
+fn test_discards_across_chunks() {
+    let inputs = [
+        "#_ #_ 1 2 3",
+        "#a #_ 1 2",
+        "#_ #a 1 2 :end",
+        "#_[1] #_{:a 1}\n#_\"s\" 3",
+        "[#_ 1 2] 3 #_ 4",
+        "1 #_",
+    ];
+    for input in &inputs {
+        let expected = read_str(input);
+        for &size in &[1, 2, 3, 64] {
+            let reader = BufReader::with_capacity(size, input.as_bytes());
+            assert_eq!(read_all(ReaderParser::new(reader)), expected);
+
+            let mut parser = PushParser::new();
+            let mut values = vec![];
+            for chunk in input.as_bytes().chunks(size) {
+                values.extend(parser.feed(chunk));
+            }
+            values.extend(parser.finish());
+            assert_eq!(values, expected, "{:?} in chunks of {}", input, size);
+        }
+    }
+
+    let mut parser = PushParser::new();
+    assert_eq!(parser.feed(b"#_ #_ 1 ").next(), None);
+    assert!(parser.needs_more());
+    assert_eq!(parser.feed(b"2 ").next(), None);
+    assert!(!parser.needs_more());
+    assert_eq!(parser.feed(b"#a #_ 1 ").next(), None);
+    assert!(parser.needs_more());
+    assert_eq!(
+        parser.feed(b"2 ").next(),
+        Some(Ok(Value::Tagged("a".into(), Box::new(Value::from(2)))))
+    );
+    assert!(!parser.needs_more());
+}
+
+#[test]
+fn test_push_values_as_soon_as_they_end() {
+    let mut parser = PushParser::new();
+    assert_eq!(parser.feed(b"[1 \"a").next(), None);
+    assert!(parser.needs_more());
+    assert_eq!(
+        parser.feed(b"]\" 2] 12").collect::<Vec<_>>(),
+        vec![Ok(Value::from(vec![
+            Value::from(1),
+            Value::from("a]"),
+            Value::from(2),
+        ]))]
+    );
+    assert!(parser.needs_more());
+
+    let mut parser = PushParser::new();
+    assert_eq!(parser.feed(b"12").next(), None);
+    assert!(parser.needs_more());
+    assert_eq!(parser.feed(b"3 ").next(), Some(Ok(Value::from(123))));
+    assert!(!parser.needs_more());
+    assert_eq!(parser.feed(b"#inst ").next(), None);
+    assert!(parser.needs_more());
+    assert_eq!(
+        parser.feed(b"\"x\"").next(),
+        Some(Ok(Value::Tagged("inst".into(), Box::new(Value::from("x")))))
+    );
+    assert_eq!(parser.feed(b"\\").next(), None);
+    assert_eq!(parser.feed(b"( :k").next(), Some(Ok(Value::from('('))));
+    assert_eq!(parser.finish().next(), Some(Ok(Value::Keyword("k".into()))));
+    assert!(!parser.needs_more());
+}
+
+#[test]
+fn test_push_finish_truncated() {
+    let mut parser = PushParser::new();
+    assert_eq!(parser.feed(b"1 2 ").count(), 2);
+    assert_eq!(parser.feed(b"{:a [1").count(), 0);
+    assert_eq!(
+        parser.finish().collect::<Vec<_>>(),
+        vec![Err(Error {
+            lo: 8,
+            hi: 10,
+            kind: ErrorKind::UnclosedDelimiter { open: "[" },
+            start: position(1, 9),
+            end: position(1, 11),
+            source_name: None,
+        })]
+    );
+
+    assert_eq!(parser.feed(b" \"abc").count(), 0);
+    assert_eq!(
+        parser.finish().collect::<Vec<_>>(),
+        vec![Err(Error {
+            lo: 11,
+            hi: 15,
+            kind: ErrorKind::UnclosedString,
+            start: position(1, 12),
+            end: position(1, 16),
+            source_name: None,
+        })]
+    );
+}
@@ -0,0 +1,97 @@
+extern crate edn;
+
+use edn::parser::Parser;
+use edn::{Spanned, SpannedValue, Value};
+
+fn read_spanned(input: &str) -> Spanned<SpannedValue> {
+    Parser::new(input).read_spanned().unwrap().unwrap()
+}
+
+fn text<'a, T>(input: &'a str, spanned: &Spanned<T>) -> &'a str {
+    &input[spanned.lo..spanned.hi]
+}
+
+#[test]
+fn test_read_spanned() {
+    let input = r#"  {:port 80, :hosts #{"a" "b"} :at #inst "2017"} ; done"#;
+    let spanned = read_spanned(input);
+    assert_eq!((spanned.lo, spanned.hi), (2, 48));
+
+    let entries = match spanned.value {
+        SpannedValue::Map(ref entries) => entries,
+        ref otherwise => panic!("unexpected {:?}", otherwise),
+    };
+    let texts: Vec<_> = entries
+        .iter()
+        .map(|(k, v)| (text(input, k), text(input, v)))
+        .collect();
+    assert_eq!(
+        texts,
+        vec![
+            (":port", "80"),
+            (":hosts", r#"#{"a" "b"}"#),
+            (":at", r#"#inst "2017""#),
+        ]
+    );
+
+    match entries[1].1.value {
+        SpannedValue::Set(ref items) => {
+            let texts: Vec<_> = items.iter().map(|item| text(input, item)).collect();
+            assert_eq!(texts, vec![r#""a""#, r#""b""#]);
+        }
+        ref otherwise => panic!("unexpected {:?}", otherwise),
+    }
+    match entries[2].1.value {
+        SpannedValue::Tagged(ref tag, ref value) => {
+            assert_eq!(tag.value, "inst");
+            assert_eq!(text(input, tag), "#inst");
+            assert_eq!(text(input, value), r#""2017""#);
+        }
+        ref otherwise => panic!("unexpected {:?}", otherwise),
+    }
+}
+
+#[test]
+fn test_read_spanned_nested() {
+    let input = "[(1 #_ 2 3) #a #b c]";
+    let spanned = read_spanned(input);
+    let items = match spanned.value {
+        SpannedValue::Vector(items) => items,
+        otherwise => panic!("unexpected {:?}", otherwise),
+    };
+    assert_eq!(text(input, &items[0]), "(1 #_ 2 3)");
+    match items[0].value {
+        SpannedValue::List(ref items) => {
+            let texts: Vec<_> = items.iter().map(|item| text(input, item)).collect();
+            assert_eq!(texts, vec!["1", "3"]);
+        }
+        ref otherwise => panic!("unexpected {:?}", otherwise),
+    }
+    assert_eq!(text(input, &items[1]), "#a #b c");
+    match items[1].value {
+        SpannedValue::Tagged(ref tag, ref value) => {
+            assert_eq!(text(input, tag), "#a");
+            assert_eq!(text(input, value), "#b c");
+        }
+        ref otherwise => panic!("unexpected {:?}", otherwise),
+    }
+}
+
+#[test]
+fn test_read_spanned_into_value() {
+    let input = r#"{:a [1 2.5 "x\ny"] :b #{\c nil true} sym #t (:k)} 42"#;
+    let mut spanned = Parser::new(input);
+    let mut plain = Parser::new(input);
+    loop {
+        match (spanned.read_spanned(), plain.read()) {
+            (Some(a), Some(b)) => assert_eq!(a.map(Value::from), b),
+            (None, None) => break,
+            otherwise => panic!("unexpected {:?}", otherwise),
+        }
+    }
+
+    assert_eq!(
+        Parser::new("[1 {").read_spanned().unwrap().map(Value::from),
+        Parser::new("[1 {").read().unwrap()
+    );
+}